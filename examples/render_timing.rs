@@ -0,0 +1,58 @@
+//! Time the headless rendering of a file on all threads against a single thread, which is how
+//! the canvas was drawn before it was parallelized:
+//!
+//!     cargo run --release --example render_timing -- FILE [ROUNDS] [-- BINOCLE OPTIONS]
+//!
+//! e.g. '-- --style entropy --zoom 1' for a style which is expensive per pixel.
+
+use std::time::Instant;
+
+use binocle::binocle::Binocle;
+use binocle::options::CliOptions;
+use binocle::settings::{HEIGHT, WIDTH};
+use clap::Parser;
+
+/// Milliseconds per frame, averaged over the rounds
+fn time_frames(binocle: &Binocle, rounds: u32) -> f64 {
+    // The first frame warms up the caches and the page cache of the file
+    binocle.render(WIDTH, HEIGHT, 1);
+    let start = Instant::now();
+    for _ in 0..rounds {
+        binocle.render(WIDTH, HEIGHT, 1);
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / rounds as f64
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let filename = args
+        .next()
+        .expect("usage: render_timing FILE [ROUNDS] [-- OPTIONS]");
+    let rounds = match args.next() {
+        Some(rounds) if rounds != "--" => rounds.parse()?,
+        _ => 10,
+    };
+    let options = CliOptions::parse_from(
+        ["binocle".to_string(), filename]
+            .into_iter()
+            .chain(args.filter(|arg| arg != "--")),
+    );
+    let binocle = Binocle::new(options, false)?;
+
+    let threads = rayon::current_num_threads();
+    let parallel = time_frames(&binocle, rounds);
+    let serial = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()?
+        .install(|| time_frames(&binocle, rounds));
+
+    println!("{}×{} pixels, {} rounds", WIDTH, HEIGHT, rounds);
+    println!("{:<12} {:8.2} ms per frame", "1 thread:", serial);
+    println!(
+        "{:<12} {:8.2} ms per frame",
+        format!("{} threads:", threads),
+        parallel
+    );
+    println!("{:<12} {:8.2}×", "speedup:", serial / parallel);
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//! Recording and replaying of the changes to the view, e.g. to reproduce bugs exactly
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{
+    FoldSettings, InterleaveSettings, NibbleMode, PixelStyle, Settings, StrideSampling,
+    StyleSettings,
+};
+
+/// The part of the settings which is changed by navigation and the controls of the GUI
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewSnapshot {
+    pub offset: i64,
+    pub offset_fine: i64,
+    pub width: isize,
+    pub stride: isize,
+    pub stride_sampling: StrideSampling,
+    pub zoom: isize,
+    pub split_view: bool,
+    pub split_delta: i64,
+    pub fold: FoldSettings,
+    pub interleave: InterleaveSettings,
+    pub reverse: bool,
+    pub nibble_mode: NibbleMode,
+    pub pixel_style: PixelStyle,
+    pub style_settings: StyleSettings,
+    pub hex_view_visible: bool,
+    pub crosshair_visible: bool,
+}
+
+impl ViewSnapshot {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            offset: settings.offset,
+            offset_fine: settings.offset_fine,
+            width: settings.width,
+            stride: settings.stride,
+            stride_sampling: settings.stride_sampling,
+            zoom: settings.zoom,
+            split_view: settings.split_view,
+            split_delta: settings.split_delta,
+            fold: settings.fold.clone(),
+            interleave: settings.interleave.clone(),
+            reverse: settings.reverse,
+            nibble_mode: settings.nibble_mode,
+            pixel_style: settings.pixel_style,
+            style_settings: settings.style_settings.clone(),
+            hex_view_visible: settings.hex_view_visible,
+            crosshair_visible: settings.crosshair_visible,
+        }
+    }
+
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.offset = self.offset;
+        settings.offset_fine = self.offset_fine;
+        settings.width = self.width;
+        settings.stride = self.stride;
+        settings.stride_sampling = self.stride_sampling;
+        settings.zoom = self.zoom;
+        settings.split_view = self.split_view;
+        settings.split_delta = self.split_delta;
+        settings.fold = self.fold.clone();
+        settings.interleave = self.interleave.clone();
+        settings.reverse = self.reverse;
+        settings.nibble_mode = self.nibble_mode;
+        settings.pixel_style = self.pixel_style;
+        settings.style_settings = self.style_settings.clone();
+        settings.hex_view_visible = self.hex_view_visible;
+        settings.crosshair_visible = self.crosshair_visible;
+    }
+}
+
+/// A single line of the action log, which is stored as JSON lines
+#[derive(Serialize, Deserialize)]
+struct LogEntry {
+    /// Milliseconds since the start of the recording
+    time_ms: u64,
+    view: ViewSnapshot,
+}
+
+/// Appends an entry to the log file whenever the view changes
+pub struct ActionRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+    last: Option<ViewSnapshot>,
+}
+
+impl ActionRecorder {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("Could not create the action log '{}'", path.display()))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            start: Instant::now(),
+            last: None,
+        })
+    }
+
+    pub fn record(&mut self, settings: &Settings) {
+        let view = ViewSnapshot::from_settings(settings);
+        if self.last.as_ref() == Some(&view) {
+            return;
+        }
+
+        let entry = LogEntry {
+            time_ms: self.start.elapsed().as_millis() as u64,
+            view,
+        };
+        // Flush every entry, such that the log is complete even after a crash
+        let result = serde_json::to_writer(&mut self.writer, &entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| Ok(writeln!(self.writer)?))
+            .and_then(|()| Ok(self.writer.flush()?));
+        if let Err(e) = result {
+            error!("Could not write the action log: {:#}", e);
+        }
+        self.last = Some(entry.view);
+    }
+}
+
+/// Applies the entries of a recorded log at the same times relative to the start of the replay
+pub struct ActionReplay {
+    entries: Vec<LogEntry>,
+    next: usize,
+    start: Instant,
+}
+
+impl ActionReplay {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Could not open the action log '{}'", path.display()))?;
+        let entries = BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|(i, line)| {
+                serde_json::from_str(&line?)
+                    .with_context(|| format!("Invalid entry in line {} of the action log", i + 1))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            entries,
+            next: 0,
+            start: Instant::now(),
+        })
+    }
+
+    /// Apply all entries which are due. Returns false once the replay is finished.
+    pub fn apply(&mut self, settings: &mut Settings) -> bool {
+        let elapsed = self.start.elapsed().as_millis() as u64;
+        while let Some(entry) = self.entries.get(self.next) {
+            if entry.time_ms > elapsed {
+                break;
+            }
+            entry.view.apply_to(settings);
+            self.next += 1;
+        }
+        self.next < self.entries.len()
+    }
+}
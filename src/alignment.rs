@@ -0,0 +1,124 @@
+//! Ranking of candidate widths by how self-similar the columns become, and detection of image
+//! data by how similar the rows are
+
+/// A multiple of a width is only ranked if its columns are more coherent than those of the width
+/// by this factor, as the multiples of a record size are about as coherent as the size itself
+const MULTIPLE_MARGIN: f64 = 1.05;
+
+/// Fraction of the bytes which are equal to the byte directly above them, when the values are
+/// laid out in rows of 'width' bytes. At the width of a record, every column holds the same field
+/// of consecutive records, which makes the columns coherent.
+pub fn column_coherence(values: &[u8], width: usize) -> f64 {
+    if width == 0 || values.len() <= width {
+        return 0.0;
+    }
+    let pairs = values.len() - width;
+    let equal = values[width..]
+        .iter()
+        .zip(values)
+        .filter(|(below, above)| below == above)
+        .count();
+    equal as f64 / pairs as f64
+}
+
+/// The 'count' widths between 'min_width' and 'max_width' with the most coherent columns, best
+/// first. Widths need at least two full rows of values to be considered.
+pub fn rank_widths(
+    values: &[u8],
+    min_width: usize,
+    max_width: usize,
+    count: usize,
+) -> Vec<(usize, f64)> {
+    let min_width = min_width.max(1);
+    let max_width = max_width.min(values.len() / 2);
+    let scores: Vec<(usize, f64)> = (min_width..=max_width)
+        .map(|width| (width, column_coherence(values, width)))
+        .collect();
+
+    let score_of = |width: usize| scores[width - min_width].1;
+    let mut ranked: Vec<(usize, f64)> = scores
+        .iter()
+        .copied()
+        .filter(|&(width, score)| {
+            score > 0.0
+                && (min_width..width)
+                    .filter(|divisor| width % divisor == 0)
+                    .all(|divisor| score > score_of(divisor) * MULTIPLE_MARGIN)
+        })
+        .collect();
+    ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    ranked.truncate(count);
+    ranked
+}
+
+/// Pearson correlation of every value with the one directly below it, when the values are laid
+/// out in rows of 'width' values. Images have strongly correlated rows, while random data does
+/// not. `None` if there are less than two rows, or no variation.
+pub fn row_correlation(values: &[u8], width: usize) -> Option<f64> {
+    if width == 0 || values.len() <= width {
+        return None;
+    }
+    let pairs = values[width..].iter().zip(values);
+    let n = (values.len() - width) as f64;
+    let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (&below, &above) in pairs {
+        let (a, b) = (above as f64, below as f64);
+        sum_a += a;
+        sum_b += b;
+        sum_aa += a * a;
+        sum_bb += b * b;
+        sum_ab += a * b;
+    }
+    let covariance = sum_ab / n - (sum_a / n) * (sum_b / n);
+    let variance_a = sum_aa / n - (sum_a / n).powi(2);
+    let variance_b = sum_bb / n - (sum_b / n).powi(2);
+    if variance_a <= 0.0 || variance_b <= 0.0 {
+        return None;
+    }
+    Some(covariance / (variance_a * variance_b).sqrt())
+}
+
+#[test]
+fn width_of_records() {
+    // Records of 24 bytes with a constant header of 4 bytes and pseudo random bytes after it
+    let mut state: u32 = 1;
+    let values: Vec<u8> = (0..4096)
+        .map(|i| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            if i % 24 < 4 {
+                0xaa
+            } else {
+                (state >> 24) as u8
+            }
+        })
+        .collect();
+
+    assert!(column_coherence(&values, 24) > 0.16);
+    assert!(column_coherence(&values, 36) < 0.05);
+    let ranked = rank_widths(&values, 2, 256, 3);
+    assert_eq!(ranked[0].0, 24);
+    assert!(ranked
+        .iter()
+        .all(|&(width, _)| width == 24 || width % 24 != 0));
+    assert!(rank_widths(&values[..8], 2, 256, 3).len() <= 3);
+}
+
+#[test]
+fn correlated_rows_of_images() {
+    // A smooth gradient image of 64 x 64 pixels, and pseudo random bytes
+    let image: Vec<u8> = (0..64 * 64)
+        .map(|i| ((i % 64) * 2 + (i / 64) * 2) as u8)
+        .collect();
+    let mut state: u32 = 1;
+    let random: Vec<u8> = (0..64 * 64)
+        .map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 24) as u8
+        })
+        .collect();
+
+    assert!(row_correlation(&image, 64).unwrap() > 0.9);
+    assert!(row_correlation(&random, 64).unwrap().abs() < 0.1);
+    assert_eq!(row_correlation(&[7; 256], 16), None);
+    assert_eq!(row_correlation(&image[..64], 64), None);
+}
@@ -0,0 +1,330 @@
+//! Summary of the whole file for scripting, built from the same detectors as the GUI
+
+use serde::Serialize;
+
+use crate::annotation;
+use crate::style;
+
+/// Size of the blocks whose entropy is compared, e.g. when jumping to the highest entropy
+const ENTROPY_BLOCK_SIZE: usize = 4096;
+
+/// The passes over the whole file proceed in chunks of this size (a multiple of the block size),
+/// such that they only touch a bounded part of a huge memory mapped file at a time
+const PASS_CHUNK_SIZE: usize = 16 << 20;
+
+/// The report lists at most this many strings, and only their first characters
+const MAX_REPORT_STRINGS: usize = 10_000;
+const MAX_REPORT_STRING_LENGTH: usize = 256;
+
+/// The entropy of every segment of the timeline is estimated from at most this many bytes at its
+/// start, to keep huge files fast
+const TIMELINE_SAMPLE_SIZE: usize = 1 << 16;
+
+/// Number of the most common byte values in the histogram summary
+const MOST_COMMON_BYTES: usize = 8;
+
+#[derive(Serialize)]
+pub struct Report {
+    pub file: String,
+    pub size: usize,
+    pub entropy: EntropySummary,
+    pub histogram: HistogramSummary,
+    pub signatures: Vec<Signature>,
+    /// Number of all strings, of which only the first ones are listed
+    pub string_count: usize,
+    pub strings: Vec<Text>,
+}
+
+#[derive(Serialize)]
+pub struct EntropySummary {
+    pub bits_per_byte: f64,
+    pub block_size: usize,
+    pub highest: Option<Block>,
+    pub lowest: Option<Block>,
+}
+
+#[derive(Serialize)]
+pub struct Block {
+    pub offset: i64,
+    pub bits_per_byte: f64,
+}
+
+#[derive(Serialize)]
+pub struct HistogramSummary {
+    pub distinct_bytes: usize,
+    pub zero_bytes: usize,
+    pub printable_bytes: usize,
+    pub most_common: Vec<ByteCount>,
+}
+
+#[derive(Serialize)]
+pub struct ByteCount {
+    pub byte: u8,
+    pub count: usize,
+}
+
+#[derive(Serialize)]
+pub struct Signature {
+    pub offset: i64,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct Text {
+    pub offset: i64,
+    pub text: String,
+}
+
+/// Running histogram and block entropies of the data, advanced chunk by chunk, such that a pass
+/// over a huge file can be spread over many frames and report its progress
+#[derive(Clone)]
+pub struct EntropyPass {
+    /// Number of bytes processed so far, at the start of a block until the end of the data
+    pub position: usize,
+    pub counts: [usize; 256],
+    /// Offsets and normalized entropies of the blocks with the highest and lowest entropy. The
+    /// last of equally high and the first of equally low blocks are kept.
+    pub highest: Option<(i64, f64)>,
+    pub lowest: Option<(i64, f64)>,
+}
+
+impl Default for EntropyPass {
+    fn default() -> Self {
+        Self {
+            position: 0,
+            counts: [0; 256],
+            highest: None,
+            lowest: None,
+        }
+    }
+}
+
+impl EntropyPass {
+    /// Process the next chunk of the data, and return whether the whole data is processed
+    pub fn advance(&mut self, data: &[u8]) -> bool {
+        let start = self.position.min(data.len());
+        let end = (start + PASS_CHUNK_SIZE).min(data.len());
+        for (block, bytes) in data[start..end].chunks(ENTROPY_BLOCK_SIZE).enumerate() {
+            let mut counts = [0; 256];
+            for &b in bytes {
+                counts[b as usize] += 1;
+            }
+            for (total, &count) in self.counts.iter_mut().zip(&counts) {
+                *total += count as usize;
+            }
+
+            let offset = (start + block * ENTROPY_BLOCK_SIZE) as i64;
+            let entropy = style::entropy_from_counts(&counts, bytes.len() as f64);
+            if self
+                .highest
+                .map_or(true, |(_, highest)| entropy.total_cmp(&highest).is_ge())
+            {
+                self.highest = Some((offset, entropy));
+            }
+            if self
+                .lowest
+                .map_or(true, |(_, lowest)| entropy.total_cmp(&lowest).is_lt())
+            {
+                self.lowest = Some((offset, entropy));
+            }
+        }
+        self.position = end;
+        self.is_done(data)
+    }
+
+    pub fn is_done(&self, data: &[u8]) -> bool {
+        self.position >= data.len()
+    }
+
+    /// Fraction of the data processed so far
+    pub fn progress(&self, data: &[u8]) -> f32 {
+        self.position as f32 / data.len().max(1) as f32
+    }
+
+    /// Normalized entropy of the bytes processed so far
+    pub fn entropy(&self) -> f64 {
+        if self.position == 0 {
+            return 0.0;
+        }
+        let total = self.position as f64;
+        let entropy: f64 = self
+            .counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total;
+                -p * p.log2()
+            })
+            .sum();
+        entropy / 8.0
+    }
+}
+
+/// Normalized entropies of 'len' segments of equal size which cover the data (fewer for small
+/// data), as an overview of the whole file
+pub fn entropy_timeline(data: &[u8], len: usize) -> Vec<f32> {
+    if data.is_empty() || len == 0 {
+        return vec![];
+    }
+    let segment_size = (data.len() + len - 1) / len;
+    data.chunks(segment_size)
+        .map(|segment| style::entropy(&segment[..segment.len().min(TIMELINE_SAMPLE_SIZE)]) as f32)
+        .collect()
+}
+
+pub fn analyze(file: &str, data: &[u8]) -> Report {
+    let mut pass = EntropyPass::default();
+    while !pass.advance(data) {}
+
+    let to_block = |(offset, entropy): (i64, f64)| Block {
+        offset,
+        bits_per_byte: entropy * 8.0,
+    };
+    let entropy = EntropySummary {
+        bits_per_byte: pass.entropy() * 8.0,
+        block_size: ENTROPY_BLOCK_SIZE,
+        highest: pass.highest.map(to_block),
+        lowest: pass.lowest.map(to_block),
+    };
+
+    let counts = pass.counts;
+    let mut most_common: Vec<ByteCount> = (0..=255u8)
+        .map(|byte| ByteCount {
+            byte,
+            count: counts[byte as usize],
+        })
+        .filter(|byte_count| byte_count.count > 0)
+        .collect();
+    most_common.sort_by_key(|byte_count| std::cmp::Reverse(byte_count.count));
+    let histogram = HistogramSummary {
+        distinct_bytes: most_common.len(),
+        zero_bytes: counts[0],
+        printable_bytes: (0..=255u8)
+            .filter(|&b| b.is_ascii_graphic() || b == b' ')
+            .map(|b| counts[b as usize])
+            .sum(),
+        most_common: most_common.into_iter().take(MOST_COMMON_BYTES).collect(),
+    };
+
+    let signatures = annotation::find(data, 0..data.len(), true, false)
+        .into_iter()
+        .map(|annotation| Signature {
+            offset: annotation.offset,
+            name: annotation.label,
+        })
+        .collect();
+
+    let strings = annotation::find_strings(data, 0..data.len());
+    Report {
+        file: file.into(),
+        size: data.len(),
+        entropy,
+        histogram,
+        signatures,
+        string_count: strings.len(),
+        strings: strings
+            .into_iter()
+            .take(MAX_REPORT_STRINGS)
+            .map(|string| Text {
+                offset: string.start as i64,
+                text: data[string]
+                    .iter()
+                    .take(MAX_REPORT_STRING_LENGTH)
+                    .map(|&b| b as char)
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+impl Report {
+    /// Short human readable form of the report
+    pub fn summary(&self) -> String {
+        let mut lines = vec![
+            format!("file: {}", self.file),
+            format!("size: {} bytes", self.size),
+            format!("entropy: {:.2} bits per byte", self.entropy.bits_per_byte),
+        ];
+        for (name, block) in [
+            ("highest", &self.entropy.highest),
+            ("lowest", &self.entropy.lowest),
+        ] {
+            if let Some(block) = block {
+                lines.push(format!(
+                    "{} block entropy: {:.2} bits per byte at 0x{:x}",
+                    name, block.bits_per_byte, block.offset
+                ));
+            }
+        }
+        lines.push(format!(
+            "distinct bytes: {}, zero bytes: {}, printable bytes: {}",
+            self.histogram.distinct_bytes,
+            self.histogram.zero_bytes,
+            self.histogram.printable_bytes
+        ));
+        for signature in &self.signatures {
+            lines.push(format!(
+                "signature: {} at 0x{:x}",
+                signature.name, signature.offset
+            ));
+        }
+        lines.push(format!("strings: {}", self.string_count));
+        lines.join("\n")
+    }
+}
+
+#[test]
+fn analyze_small_data() {
+    let mut data = vec![0u8; 64];
+    data[0..4].copy_from_slice(b"\x7fELF");
+    data[20..40].copy_from_slice(b"Hello, binocle world");
+
+    let report = analyze("test", &data);
+    assert_eq!(report.size, 64);
+    assert_eq!(report.histogram.zero_bytes, 40);
+    assert_eq!(report.histogram.most_common[0].byte, 0);
+    assert_eq!(report.signatures[0].name, "ELF");
+    assert_eq!(report.strings[0].offset, 20);
+    assert_eq!(report.strings[0].text, "Hello, binocle world");
+    assert_eq!(report.entropy.highest.as_ref().unwrap().offset, 0);
+
+    let report = analyze("empty", &[]);
+    assert_eq!(report.entropy.bits_per_byte, 0.0);
+    assert!(report.entropy.highest.is_none());
+}
+
+#[test]
+fn timeline_covers_data() {
+    let mut data = vec![0u8; 1000];
+    data[500..]
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, b)| *b = i as u8);
+
+    let timeline = entropy_timeline(&data, 4);
+    assert_eq!(timeline.len(), 4);
+    assert_eq!(timeline[0], 0.0);
+    assert!(timeline[3] > 0.8);
+    assert_eq!(entropy_timeline(&data[..3], 8).len(), 3);
+    assert!(entropy_timeline(&[], 8).is_empty());
+}
+
+#[test]
+fn entropy_pass_in_chunks() {
+    // Blocks of constant bytes, except for one of all byte values in the second chunk
+    let mut data = vec![7u8; PASS_CHUNK_SIZE + 3 * ENTROPY_BLOCK_SIZE + 10];
+    let ramp_offset = PASS_CHUNK_SIZE + ENTROPY_BLOCK_SIZE;
+    data[ramp_offset..ramp_offset + ENTROPY_BLOCK_SIZE]
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, b)| *b = i as u8);
+
+    let mut pass = EntropyPass::default();
+    assert!(!pass.advance(&data));
+    assert_eq!(pass.position, PASS_CHUNK_SIZE);
+    assert!(pass.advance(&data));
+    assert_eq!(pass.highest, Some((ramp_offset as i64, 1.0)));
+    assert_eq!(pass.lowest, Some((0, 0.0)));
+    assert_eq!(pass.counts[7], data.len() - ENTROPY_BLOCK_SIZE + 16);
+    assert!((pass.entropy() - style::entropy(&data)).abs() < 1e-9);
+}
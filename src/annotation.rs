@@ -0,0 +1,146 @@
+//! Detection of known structures in the data, to be labeled on the canvas
+
+use std::ops::Range;
+
+/// Strings need at least this many printable ASCII characters to be labeled
+const MIN_STRING_LENGTH: usize = 8;
+
+/// Labels of strings show at most this many characters of the string
+const MAX_STRING_LABEL_LENGTH: usize = 12;
+
+/// Magic numbers at the start of common file formats
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x7fELF", "ELF"),
+    (b"\x89PNG\r\n\x1a\n", "PNG"),
+    (b"PK\x03\x04", "ZIP"),
+    (b"\x1f\x8b\x08", "GZIP"),
+    (b"\x28\xb5\x2f\xfd", "ZSTD"),
+    (b"7z\xbc\xaf\x27\x1c", "7Z"),
+    (b"%PDF-", "PDF"),
+    (b"\xff\xd8\xff", "JPEG"),
+    (b"GIF8", "GIF"),
+    (b"SQLite format 3\0", "SQLITE"),
+];
+
+/// A position or size on the canvas, in pixels
+pub type Vec2 = (isize, isize);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AnnotationKind {
+    Signature,
+    String,
+}
+
+pub struct Annotation {
+    pub offset: i64,
+    pub kind: AnnotationKind,
+    pub label: String,
+}
+
+/// Signatures and strings which start within 'range' of the data, in the order of their offset
+pub fn find(data: &[u8], range: Range<usize>, signatures: bool, strings: bool) -> Vec<Annotation> {
+    let range = range.start.min(data.len())..range.end.min(data.len());
+    let mut annotations = vec![];
+
+    if signatures {
+        for offset in range.clone() {
+            for (magic, name) in SIGNATURES {
+                if data[offset..].starts_with(magic) {
+                    annotations.push(Annotation {
+                        offset: offset as i64,
+                        kind: AnnotationKind::Signature,
+                        label: (*name).into(),
+                    });
+                }
+            }
+        }
+    }
+
+    if strings {
+        for string in find_strings(data, range) {
+            let text: String = data[string.clone()]
+                .iter()
+                .take(MAX_STRING_LABEL_LENGTH)
+                .map(|&b| b as char)
+                .collect();
+            annotations.push(Annotation {
+                offset: string.start as i64,
+                kind: AnnotationKind::String,
+                label: format!("\"{}\"", text),
+            });
+        }
+    }
+
+    annotations.sort_by_key(|annotation| annotation.offset);
+    annotations
+}
+
+/// Ranges of the runs of at least MIN_STRING_LENGTH printable ASCII characters which start within
+/// 'range' of the data. A string which started before the range is cut off at its start.
+pub fn find_strings(data: &[u8], range: Range<usize>) -> Vec<Range<usize>> {
+    let range = range.start.min(data.len())..range.end.min(data.len());
+    let is_printable = |b: u8| b.is_ascii_graphic() || b == b' ';
+    let mut strings = vec![];
+    let mut offset = range.start;
+    while offset < range.end {
+        let length = data[offset..]
+            .iter()
+            .take_while(|&&b| is_printable(b))
+            .count();
+        if length >= MIN_STRING_LENGTH {
+            strings.push(offset..offset + length);
+        }
+        offset += length.max(1);
+    }
+    strings
+}
+
+/// Simple collision avoidance for labels of the given sizes, each anchored at a point: a label
+/// is placed to the right of its anchor, or moved down in steps of its height until it does not
+/// overlap any of the previous labels. Labels which do not find a place are left out.
+pub fn place_labels(labels: &[(Vec2, Vec2)], max_steps: isize) -> Vec<Option<Vec2>> {
+    let mut placed: Vec<(isize, isize, isize, isize)> = vec![];
+    labels
+        .iter()
+        .map(|&((x, y), (width, height))| {
+            let overlaps = |top: isize| {
+                placed.iter().any(|&(left, other_top, right, bottom)| {
+                    x < right && left < x + width && top < bottom && other_top < top + height
+                })
+            };
+            let top = (0..=max_steps)
+                .map(|step| y + step * height)
+                .find(|&top| !overlaps(top))?;
+            placed.push((x, top, x + width, top + height));
+            Some((x, top))
+        })
+        .collect()
+}
+
+#[test]
+fn find_signatures_and_strings() {
+    let mut data = vec![0u8; 64];
+    data[4..8].copy_from_slice(b"\x7fELF");
+    data[20..40].copy_from_slice(b"Hello, binocle world");
+    data[50..54].copy_from_slice(b"abcd");
+
+    let annotations = find(&data, 0..64, true, true);
+    let found: Vec<_> = annotations
+        .iter()
+        .map(|a| (a.offset, a.label.as_str()))
+        .collect();
+    assert_eq!(found, [(4, "ELF"), (20, "\"Hello, binoc\"")]);
+
+    assert!(find(&data, 0..64, false, false).is_empty());
+    assert_eq!(find(&data, 30..64, false, true)[0].offset, 30);
+}
+
+#[test]
+fn labels_avoid_each_other() {
+    let size = (20, 10);
+    let positions = place_labels(&[((0, 0), size), ((5, 2), size), ((50, 0), size)], 1);
+    assert_eq!(positions, [Some((0, 0)), Some((5, 12)), Some((50, 0))]);
+
+    let positions = place_labels(&[((0, 0), size), ((0, 0), size), ((0, 0), size)], 1);
+    assert_eq!(positions, [Some((0, 0)), Some((0, 10)), None]);
+}
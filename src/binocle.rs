@@ -1,34 +1,715 @@
-use anyhow::Result;
+use std::borrow::Cow;
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Mutex;
 
-use crate::buffer::Buffer;
+use anyhow::{bail, Context, Result};
+use log::warn;
+use rayon::prelude::*;
+
+use crate::alignment;
+use crate::analysis::{self, EntropyPass};
+use crate::annotation::{self, AnnotationKind};
+use crate::buffer::{self, Buffer, Compression};
+use crate::clipboard::Clipboard;
 use crate::datatype::Datatype;
+use crate::download;
+use crate::editor;
+use crate::export::{self, Image};
+use crate::font;
+use crate::headless;
+use crate::inspector;
+use crate::loader::Loader;
 use crate::options::{BackingOption, CliOptions};
-use crate::settings::{GuiDatatype, PixelStyle, Settings, WIDTH};
+use crate::region::{Region, RegionMap};
+use crate::search;
+use crate::settings::{
+    ColorScale, EntropyJump, FoldMode, GridPosition, GuiDatatype, LoadChoice, LoadingState,
+    NibbleMode, PinnedRow, PixelStyle, Regions, Settings, StrideSampling, VersionSettings,
+};
+use crate::spectrum;
 use crate::style::{
-    Abgr, Bgr, Category, ColorGradient, Colorful, DatatypeStyle, Entropy, Grayscale, Rgb, Rgba,
-    Style,
+    run_length_levels, Abgr, Bgr, Category, Color, ColorGradient, Colorful, DatatypeStyle, Delta,
+    Entropy, Grayscale, Pointer, Position, Relief, Rgb, Rgba, RunLength, Style,
 };
 use crate::view::View;
 
+/// Size of the longer side of the export preview thumbnail, in pixels
+const PREVIEW_SIZE: u32 = 256;
+
+/// Labels of annotations are moved down at most this many times to avoid overlaps
+const ANNOTATION_LABEL_STEPS: isize = 3;
+
+/// Upper limits for the number of sparklines of the bytes of a record, and their length
+const MAX_SPARKLINES: isize = 64;
+const MAX_SPARKLINE_RECORDS: i64 = 1024;
+
+/// Number of segments of the file in the entropy timeline
+const ENTROPY_TIMELINE_LENGTH: usize = 1024;
+
+/// Upper limit for the number of visible bytes which are searched for signatures and strings
+const MAX_ANNOTATION_LENGTH: i64 = 1 << 20;
+
+/// Upper limit for the number of visible bytes the spectrum is computed from
+const MAX_SPECTRUM_LENGTH: isize = 1 << 16;
+
+/// Upper limit for the number of visible bytes the widths are scored on, and the number of the
+/// best widths which are listed
+const MAX_WIDTH_SCORE_LENGTH: isize = 1 << 15;
+const WIDTH_CANDIDATES: usize = 8;
+
+/// Upper limit for the number of visible bytes the correlation of the rows is measured on
+const MAX_CORRELATION_LENGTH: isize = 1 << 16;
+
+/// The run length levels of larger buffers are not cached, as they take one byte per byte
+const MAX_RUN_LENGTH_CACHE_SIZE: usize = 256 << 20;
+
+/// Searching stops after this many matches, to keep patterns like '00' manageable
+const MAX_SEARCH_MATCHES: usize = 100_000;
+
+/// Files above this size are only read into memory after asking, and then in the background
+const LARGE_FILE_SIZE: u64 = 1 << 30;
+
+/// Downloads above this size or of unknown size only start after asking
+const LARGE_DOWNLOAD_SIZE: u64 = 64 << 20;
+
+/// Height of the bars indicating the beginning and end of the file, in pixels
+const LIMIT_INDICATOR_SIZE: usize = 3;
+
+/// Color of the line below the pinned row, which separates it from the view
+const PINNED_ROW_SEPARATOR_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// Distance between the diagonal lines of the hatch shown while the stride skips bytes, and how
+/// much they darken the pixels (out of 256)
+const STRIDE_HATCH_SPACING: isize = 8;
+const STRIDE_HATCH_BRIGHTNESS: u16 = 160;
+
+/// Selected bytes are mixed with this color
+const SELECTION_COLOR: [u8; 3] = [0, 160, 255];
+
+/// Printable ASCII bytes are mixed with this color in the text overlay
+const TEXT_OVERLAY_COLOR: [u8; 3] = [0, 255, 0];
+
+/// Mix a pixel half and half with the given color and make it opaque
+fn tint(pixel: [u8; 4], tint: [u8; 3]) -> [u8; 4] {
+    let mix = |channel: u8, tint: u8| ((channel as u16 + tint as u16) / 2) as u8;
+    [
+        mix(pixel[0], tint[0]),
+        mix(pixel[1], tint[1]),
+        mix(pixel[2], tint[2]),
+        255,
+    ]
+}
+
 pub struct Binocle {
     pub settings: Settings,
     buffer: Buffer,
+    /// Name of the shown data: the file name, or a description like '<clipboard>'
+    filename: String,
+    /// The file the data was read from, `None` for data which is not backed by a file
+    path: Option<String>,
+    /// Sorted ranges of the file without any backing data
+    holes: Vec<Range<i64>>,
+    /// Levels of the whole buffer for the run length style, computed on its first use unless the
+    /// buffer is too large
+    run_length_levels: Option<Vec<u8>>,
+    /// Reads a large file in the background, and the offset to show once it is done
+    loader: Option<Loader>,
+    pending_offset: i64,
+    /// Large files which are opened later are not read without asking either, like at the start
+    defer_large_files: bool,
+    clipboard: Clipboard,
+    /// The versions of the file which are not shown, starting with the next one
+    other_versions: Vec<Version>,
+    /// Body of the download of the file, which is read once the user agrees to its size (in a
+    /// mutex, as the drawing threads share the binocle)
+    pending_download: Option<Mutex<Box<dyn Read + Send>>>,
+    /// Block entropies of the whole buffer, computed over several frames for the requested jump
+    entropy_pass: EntropyPass,
+    pending_entropy_jump: Option<EntropyJump>,
+}
+
+/// Another version of the file, which is swapped with the shown one
+struct Version {
+    filename: String,
+    path: Option<String>,
+    buffer: Buffer,
+    holes: Vec<Range<i64>>,
+    /// Offset in the original file, for the bytes of a selection which were detached
+    detached_from: Option<i64>,
+    regions: RegionMap,
+    /// The regions changed since they were loaded or saved
+    regions_modified: bool,
+}
+
+impl Version {
+    fn open(filename: &str, backing: BackingOption) -> Result<Self> {
+        let context = || format!("Could not read '{}'", filename);
+        let (buffer, holes) = match Compression::detect(filename).with_context(context)? {
+            Some(compression) => (read_compressed(filename, compression), vec![]),
+            None => {
+                let buffer = match backing {
+                    BackingOption::File => Buffer::from_file(filename),
+                    BackingOption::Mmap => Buffer::from_mmap(filename),
+                };
+                (buffer, find_holes(filename))
+            }
+        };
+        Ok(Self {
+            filename: filename.into(),
+            path: Some(filename.into()),
+            buffer: buffer.with_context(context)?,
+            holes,
+            detached_from: None,
+            regions: load_regions(Some(filename)),
+            regions_modified: false,
+        })
+    }
+
+    /// An empty version of a file which is too large to be read into memory without asking, or
+    /// `None` for other files. Its data is read by 'update_loading'.
+    fn deferred(filename: &str) -> Result<Option<(Self, u64)>> {
+        let context = || format!("Could not read '{}'", filename);
+        if Compression::detect(filename)
+            .with_context(context)?
+            .is_some()
+        {
+            return Ok(None);
+        }
+        let file_size = std::fs::metadata(filename).with_context(context)?.len();
+        if file_size <= LARGE_FILE_SIZE {
+            return Ok(None);
+        }
+        let version = Self {
+            filename: filename.into(),
+            path: Some(filename.into()),
+            buffer: Buffer::VecBuffer(vec![]),
+            holes: find_holes(filename),
+            detached_from: None,
+            regions: load_regions(Some(filename)),
+            regions_modified: false,
+        };
+        Ok(Some((version, file_size)))
+    }
+}
+
+/// Decompress the file, or fall back to its raw bytes
+fn read_compressed(filename: &str, compression: Compression) -> std::io::Result<Buffer> {
+    Buffer::from_compressed_file(filename, compression).or_else(|e| {
+        warn!(
+            "Could not decompress '{}' ({:?}): {}. Showing the raw bytes instead.",
+            filename, compression, e
+        );
+        Buffer::from_file(filename)
+    })
+}
+
+fn find_holes(filename: &str) -> Vec<Range<i64>> {
+    buffer::find_holes(filename).unwrap_or_else(|e| {
+        warn!("Could not find the holes of '{}': {}", filename, e);
+        vec![]
+    })
+}
+
+/// The regions of the file from its sidecar file. If it can not be read, there are none, like for
+/// data which is not backed by a file.
+fn load_regions(path: Option<&str>) -> RegionMap {
+    let path = match path {
+        Some(path) => path,
+        None => return RegionMap::default(),
+    };
+    RegionMap::load(path).unwrap_or_else(|e| {
+        warn!("{:#}", e);
+        RegionMap::default()
+    })
 }
 
 impl Binocle {
-    pub fn new(options: CliOptions) -> Result<Self> {
-        let buffer = match options.backing {
-            BackingOption::File => Buffer::from_file(options.filename),
-            BackingOption::Mmap => Buffer::from_mmap(options.filename),
-        }?;
+    /// Open the file given in the options. With 'defer_large_files', files above a size limit
+    /// which would be read into memory are not opened yet: the user can choose between reading
+    /// them in the background or mapping them into memory (see 'update_loading').
+    pub fn new(options: CliOptions, defer_large_files: bool) -> Result<Self> {
+        let filename = &options.filename;
+        let context = || format!("Could not read '{}'", filename);
+        let mut holes = vec![];
+        let mut deferred_size = None;
+        let mut download = None;
+        let buffer = if download::is_url(filename) {
+            let started = download::start(filename)
+                .with_context(|| format!("Could not download '{}'", filename))?;
+            if defer_large_files {
+                // Downloads are always read in the background, and only start on their own if
+                // they are small
+                deferred_size = Some(started.size.unwrap_or(0));
+                download = Some(started);
+                Ok(Buffer::VecBuffer(vec![]))
+            } else {
+                let mut data = vec![];
+                let mut reader = started.reader;
+                reader
+                    .read_to_end(&mut data)
+                    .map(|_| Buffer::VecBuffer(data))
+            }
+        } else if buffer::is_device(filename).with_context(context)? {
+            if !options.allow_devices {
+                bail!(
+                    "'{}' is a device. Pass --allow-devices to open it anyway.",
+                    filename
+                );
+            }
+            Buffer::from_device(filename)
+        } else {
+            match Compression::detect(filename).with_context(context)? {
+                Some(compression) => read_compressed(filename, compression),
+                None => {
+                    holes = find_holes(filename);
+                    let file_size = std::fs::metadata(filename).with_context(context)?.len();
+                    match options.backing {
+                        BackingOption::File if defer_large_files && file_size > LARGE_FILE_SIZE => {
+                            deferred_size = Some(file_size);
+                            Ok(Buffer::VecBuffer(vec![]))
+                        }
+                        BackingOption::File => Buffer::from_file(filename),
+                        BackingOption::Mmap => Buffer::from_mmap(filename),
+                    }
+                }
+            }
+        }
+        .with_context(context)?;
+
+        let versions = options
+            .other_files
+            .iter()
+            .map(|filename| Version::open(filename, options.backing))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Downloads can not be read again, or opened in the editor
+        let path = (!download::is_url(filename)).then(|| filename.clone());
+        let regions = Regions {
+            map: load_regions(path.as_deref()),
+            ..Regions::default()
+        };
+
+        let defaults = Settings::new(buffer.len() as i64);
+        let mut settings = Settings {
+            width: options.width.unwrap_or(defaults.width),
+            offset: options.offset.unwrap_or(defaults.offset),
+            stride: options.stride.unwrap_or(defaults.stride),
+            zoom: options.zoom.unwrap_or(defaults.zoom),
+            pixel_style: options.style.unwrap_or(defaults.pixel_style),
+            hole_length: holes.iter().map(|hole| hole.end - hole.start).sum(),
+            address_base: options.base_address.unwrap_or(0),
+            show_addresses: options.base_address.is_some(),
+            versions: VersionSettings {
+                count: versions.len() + 1,
+                current: 0,
+                name: filename.clone(),
+                step_requested: None,
+                detached_from: None,
+                detach_requested: false,
+                file_backed: path.is_some(),
+            },
+            loading: deferred_size.map(|file_size| LoadingState {
+                file_size,
+                bytes_read: 0,
+                started: false,
+                choice: download
+                    .as_ref()
+                    .filter(|_| file_size > 0 && file_size <= LARGE_DOWNLOAD_SIZE)
+                    .map(|_| LoadChoice::Read),
+                download: download.is_some(),
+            }),
+            regions,
+            ..defaults
+        };
+        settings.clamp();
+        settings.keep_as_startup();
+
+        Ok(Self {
+            buffer,
+            path,
+            settings,
+            filename: options.filename,
+            holes,
+            run_length_levels: None,
+            loader: None,
+            pending_offset: options.offset.unwrap_or(0),
+            defer_large_files,
+            clipboard: Clipboard::default(),
+            other_versions: versions,
+            pending_download: download.map(|download| Mutex::new(download.reader)),
+            entropy_pass: EntropyPass::default(),
+            pending_entropy_jump: None,
+        })
+    }
+
+    /// View of data in memory instead of a file, e.g. for tests
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        let mut settings = Settings::new(data.len() as i64);
+        settings.clamp();
+
+        Self {
+            buffer: Buffer::VecBuffer(data),
+            settings,
+            filename: "<memory>".into(),
+            path: None,
+            holes: vec![],
+            run_length_levels: None,
+            loader: None,
+            pending_offset: 0,
+            defer_large_files: false,
+            clipboard: Clipboard::default(),
+            other_versions: vec![],
+            pending_download: None,
+            entropy_pass: EntropyPass::default(),
+            pending_entropy_jump: None,
+        }
+    }
+
+    /// The data up to the buffer length of the settings, which leaves out trimmed padding
+    fn data(&self) -> &[u8] {
+        let data = self.buffer.data();
+        &data[..(self.settings.buffer_length.max(0) as usize).min(data.len())]
+    }
+
+    /// Summary of the whole data, see '--analyze'
+    pub(crate) fn analyze(&self) -> analysis::Report {
+        analysis::analyze(&self.filename, self.buffer.data())
+    }
+
+    /// Open a deferred large file in the way the user chose, and track the progress of reading it
+    pub fn update_loading(&mut self) {
+        let loading = match &mut self.settings.loading {
+            Some(loading) => loading,
+            None => return,
+        };
+
+        match loading.choice.take() {
+            Some(LoadChoice::Read) => {
+                self.loader = Some(match self.pending_download.take() {
+                    Some(reader) => Loader::from_reader(
+                        reader.into_inner().unwrap_or_else(|e| e.into_inner()),
+                        loading.file_size,
+                    ),
+                    None => Loader::start(self.filename.clone().into()),
+                });
+                loading.started = true;
+            }
+            Some(LoadChoice::Mmap) => {
+                let result = Buffer::from_mmap(&self.filename);
+                self.finish_loading(result);
+                return;
+            }
+            Some(LoadChoice::Cancel) => {
+                self.pending_download = None;
+                self.settings.loading = None;
+                self.settings.status = format!("Did not download '{}'", self.filename);
+                return;
+            }
+            None => {}
+        }
+
+        if let Some(loader) = &mut self.loader {
+            loading.bytes_read = loader.bytes_read();
+            if let Some(result) = loader.try_finish() {
+                self.loader = None;
+                self.finish_loading(result.map(Buffer::VecBuffer));
+            }
+        }
+    }
+
+    fn finish_loading(&mut self, result: std::io::Result<Buffer>) {
+        self.settings.loading = None;
+        match result {
+            Ok(buffer) => self.replace_buffer(buffer, self.pending_offset),
+            Err(e) => self.settings.status = format!("Could not read '{}': {}", self.filename, e),
+        }
+    }
+
+    /// Show other data, starting at the given offset, and drop everything derived from the old one
+    fn replace_buffer(&mut self, buffer: Buffer, offset: i64) {
+        let settings = &mut self.settings;
+        settings.buffer_length = buffer.len() as i64;
+        settings.split_delta = settings.buffer_length / 2;
+        settings.offset = offset;
+        settings.offset_fine = 0;
+        settings.search.matches.clear();
+        settings.spectrum.source = None;
+        settings.annotations.source = None;
+        settings.width_scores.source = None;
+        settings.entropy_timeline.entropies.clear();
+        settings.padding.source = None;
+        settings.clamp();
+        self.buffer = buffer;
+        self.run_length_levels = None;
+        self.entropy_pass = EntropyPass::default();
+    }
+
+    /// Read the file again if requested, e.g. after another program changed it, with the same
+    /// view. If it can not be read, the old data stays.
+    pub fn update_reload(&mut self) {
+        if !std::mem::take(&mut self.settings.reload_requested) || self.settings.loading.is_some() {
+            return;
+        }
+
+        let backing = match self.buffer {
+            Buffer::VecBuffer(_) => BackingOption::File,
+            Buffer::MmapBuffer(_) => BackingOption::Mmap,
+        };
+        let path = match &self.path {
+            Some(path) => path,
+            None => {
+                self.settings.status = format!("{} is not a file to reload", self.filename);
+                return;
+            }
+        };
+        let version = match Version::open(path, backing) {
+            Ok(version) => version,
+            Err(e) => {
+                self.settings.status = format!("{:#}", e);
+                return;
+            }
+        };
 
-        let buffer_length = buffer.len();
-        let settings = Settings {
-            buffer_length: buffer_length as isize,
-            ..Default::default()
+        let offset = self.settings.offset;
+        let offset_fine = self.settings.offset_fine;
+        self.replace_buffer(version.buffer, offset);
+        self.holes = version.holes;
+
+        let settings = &mut self.settings;
+        settings.offset_fine = offset_fine;
+        settings.hole_length = self.holes.iter().map(|hole| hole.end - hole.start).sum();
+        settings.clamp();
+        settings.status = format!(
+            "Reloaded {} ({} bytes)",
+            self.filename, settings.buffer_length
+        );
+    }
+
+    /// Show another file instead of the current one, e.g. one dropped on the window. Unless the
+    /// layout is kept, the view starts over with the default layout.
+    pub fn open(&mut self, filename: &str) -> Result<()> {
+        if self.settings.loading.is_some() {
+            bail!(
+                "Could not open '{}' while '{}' is loaded",
+                filename,
+                self.filename
+            );
+        }
+        let backing = match self.buffer {
+            Buffer::VecBuffer(_) => BackingOption::File,
+            Buffer::MmapBuffer(_) => BackingOption::Mmap,
+        };
+        let deferred = match backing {
+            BackingOption::File if self.defer_large_files => Version::deferred(filename)?,
+            _ => None,
         };
+        let (version, deferred_size) = match deferred {
+            Some((version, file_size)) => (version, Some(file_size)),
+            None => (Version::open(filename, backing)?, None),
+        };
+
+        let keep_layout = self.settings.keep_layout;
+        let offset_fine = self.settings.offset_fine;
+        let offset = if keep_layout { self.settings.offset } else { 0 };
+        self.replace_buffer(version.buffer, offset);
+        self.filename = version.filename;
+        self.path = version.path;
+        self.holes = version.holes;
+        self.other_versions.clear();
+        self.pending_offset = offset;
+
+        let settings = &mut self.settings;
+        settings.loading = deferred_size.map(|file_size| LoadingState {
+            file_size,
+            bytes_read: 0,
+            started: false,
+            choice: None,
+            download: false,
+        });
+        if keep_layout {
+            settings.offset_fine = offset_fine;
+        } else {
+            let defaults = Settings::new(settings.buffer_length);
+            settings.width = defaults.width;
+            settings.stride = defaults.stride;
+            settings.zoom = defaults.zoom;
+            settings.address_base = defaults.address_base;
+            settings.show_addresses = defaults.show_addresses;
+        }
+        settings.hole_length = self.holes.iter().map(|hole| hole.end - hole.start).sum();
+        settings.selection = None;
+        settings.regions.map = version.regions;
+        settings.regions.modified = false;
+        settings.versions = VersionSettings {
+            count: 1,
+            current: 0,
+            name: self.filename.clone(),
+            step_requested: None,
+            detached_from: None,
+            detach_requested: false,
+            file_backed: true,
+        };
+        settings.clamp();
+        settings.status = format!("Opened {}", self.filename);
+        Ok(())
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Show a copy of the selected bytes as a version of its own, which starts at offset 0. The
+    /// shown version stays available as the previous one.
+    pub fn update_detach(&mut self) {
+        if !std::mem::take(&mut self.settings.versions.detach_requested) {
+            return;
+        }
+        let range = match self.settings.selection {
+            Some(selection) => selection.range(),
+            None => return,
+        };
+        let data = self.data();
+        let start = range.start.clamp(0, data.len() as i64) as usize;
+        let end = range.end.clamp(0, data.len() as i64) as usize;
+        let detached_from = self.settings.versions.detached_from.unwrap_or(0) + start as i64;
+        let filename = format!("{} [0x{:x}..0x{:x}]", self.filename, start, end);
+        let buffer = Buffer::VecBuffer(data[start..end].to_vec());
+
+        // The versions are numbered in the order of the ring, so the detached version goes right
+        // after the shown one, which becomes the last of the others
+        let shown = Version {
+            // The name of the detached bytes describes them, they are not backed by a file
+            path: self.path.take(),
+            filename: std::mem::replace(&mut self.filename, filename),
+            buffer: std::mem::replace(&mut self.buffer, Buffer::VecBuffer(vec![])),
+            holes: std::mem::take(&mut self.holes),
+            detached_from: self.settings.versions.detached_from,
+            // The offsets of the regions refer to the file, the detached bytes start over
+            regions: std::mem::take(&mut self.settings.regions.map),
+            regions_modified: std::mem::take(&mut self.settings.regions.modified),
+        };
+        self.other_versions.push(shown);
+        self.replace_buffer(buffer, 0);
+
+        let settings = &mut self.settings;
+        settings.selection = None;
+        settings.hole_length = 0;
+        let versions = &mut settings.versions;
+        versions.count = self.other_versions.len() + 1;
+        versions.current += 1;
+        versions.name = self.filename.clone();
+        versions.detached_from = Some(detached_from);
+        versions.file_backed = false;
+        settings.status = format!(
+            "Detached {} bytes from {}",
+            end - start,
+            settings.display_offset(detached_from)
+        );
+    }
+
+    /// Show the requested other version of the file, with the same view
+    pub fn update_versions(&mut self) {
+        let step = match self.settings.versions.step_requested.take() {
+            Some(step) if !self.other_versions.is_empty() => step,
+            _ => return,
+        };
+
+        for _ in 0..step.unsigned_abs() {
+            // The other versions are a ring which starts after the shown one
+            let others = &mut self.other_versions;
+            let other = if step > 0 { 0 } else { others.len() - 1 };
+            std::mem::swap(&mut self.buffer, &mut others[other].buffer);
+            std::mem::swap(&mut self.filename, &mut others[other].filename);
+            std::mem::swap(&mut self.path, &mut others[other].path);
+            std::mem::swap(&mut self.holes, &mut others[other].holes);
+            std::mem::swap(
+                &mut self.settings.versions.detached_from,
+                &mut others[other].detached_from,
+            );
+            std::mem::swap(&mut self.settings.regions.map, &mut others[other].regions);
+            std::mem::swap(
+                &mut self.settings.regions.modified,
+                &mut others[other].regions_modified,
+            );
+            if step > 0 {
+                others.rotate_left(1);
+            } else {
+                others.rotate_right(1);
+            }
+        }
+
+        let offset = self.settings.offset;
+        let offset_fine = self.settings.offset_fine;
+        let buffer = std::mem::replace(&mut self.buffer, Buffer::VecBuffer(vec![]));
+        self.replace_buffer(buffer, offset);
+
+        let settings = &mut self.settings;
+        settings.offset_fine = offset_fine;
+        settings.hole_length = self.holes.iter().map(|hole| hole.end - hole.start).sum();
+        settings.clamp();
+        let versions = &mut settings.versions;
+        versions.current =
+            (versions.current as isize + step).rem_euclid(versions.count as isize) as usize;
+        versions.name = self.filename.clone();
+        versions.file_backed = self.path.is_some();
+        settings.status = format!(
+            "Version {} of {}: {}",
+            versions.current + 1,
+            versions.count,
+            versions.name
+        );
+    }
+
+    /// Replace the data with the bytes from the clipboard, or copy the view to the clipboard as an
+    /// image, if requested
+    pub fn update_clipboard(&mut self) {
+        if std::mem::take(&mut self.settings.copy_image_requested) {
+            let settings = &self.settings;
+            let (width, height) = (settings.canvas_width, settings.canvas_height);
+            let frame = headless::render_to_vec(self);
+            self.settings.status =
+                match self
+                    .clipboard
+                    .copy_image(width as usize, height as usize, &frame)
+                {
+                    Ok(()) => format!("Copied the view ({}×{}) to the clipboard", width, height),
+                    Err(e) => e,
+                };
+        }
+
+        if !std::mem::take(&mut self.settings.clipboard_requested) {
+            return;
+        }
+        // The file which is read in the background would replace the data from the clipboard
+        if self.settings.loading.is_some() {
+            self.settings.status = "The file is still being opened".into();
+            return;
+        }
 
-        Ok(Self { buffer, settings })
+        match self.clipboard.read() {
+            Ok(data) => {
+                let len = data.len();
+                self.replace_buffer(Buffer::VecBuffer(data), 0);
+                self.filename = "<clipboard>".into();
+                self.path = None;
+                self.holes.clear();
+                let settings = &mut self.settings;
+                settings.hole_length = 0;
+                settings.selection = None;
+                settings.regions.map = RegionMap::default();
+                settings.regions.modified = false;
+                settings.versions.name = self.filename.clone();
+                settings.versions.file_backed = false;
+                settings.status = format!("Loaded {} bytes from the clipboard", len);
+            }
+            Err(e) => self.settings.status = e,
+        }
     }
 
     pub fn update_hex_view(&mut self) {
@@ -39,15 +720,17 @@ impl Binocle {
         let mut hex_view = String::new();
         let mut hex_ascii = String::new();
 
-        let view = View::new(
-            self.buffer.data(),
-            self.settings.offset + self.settings.offset_fine,
-            1,
-        );
-
         let width = (self.settings.width * self.settings.stride).min(36);
         let height = 24;
 
+        let transformed;
+        let view = if self.settings.hex_view_transformed && self.settings.is_transformed() {
+            transformed = self.transformed_data((width * height) as i64);
+            View::new(&transformed, 0, 1)
+        } else {
+            View::new(self.data(), self.settings.view_start(), 1)
+        };
+
         for i in 0..(width * height) {
             if i > 0 && i % width == 0 {
                 hex_view.push('\n');
@@ -73,36 +756,557 @@ impl Binocle {
         self.settings.hex_ascii = hex_ascii;
     }
 
-    pub fn draw(&self, frame: &mut [u8]) {
+    /// Interpret the first bytes of the selection, which is cheap enough to do on every frame
+    pub fn update_inspector(&mut self) {
+        let settings = &self.settings;
+        let data = self.data();
+        let interpretations = match settings.selection {
+            Some(selection) if settings.inspector.visible => {
+                let range = selection.range();
+                let length = (range.end - range.start).min(inspector::TEXT_LENGTH as i64);
+                let bytes: Vec<u8> = (range.start..range.start + length)
+                    .map_while(|offset| {
+                        usize::try_from(offset)
+                            .ok()
+                            .and_then(|offset| data.get(offset))
+                            .copied()
+                    })
+                    .collect();
+                inspector::interpretations(&bytes)
+            }
+            _ => vec![],
+        };
+        self.settings.inspector.interpretations = interpretations;
+    }
+
+    /// Leave the trailing run of padding bytes out of the view, or show it again
+    pub fn update_padding(&mut self) {
+        let padding = &self.settings.padding;
+        let source = Some((padding.enabled, padding.byte));
+        if padding.source == source {
+            return;
+        }
+
+        let data = self.buffer.data();
+        let trimmed = if padding.enabled {
+            data.iter()
+                .rev()
+                .take_while(|&&b| b == padding.byte)
+                .count()
+        } else {
+            0
+        };
+        let settings = &mut self.settings;
+        settings.padding.trimmed = trimmed as i64;
+        settings.padding.source = source;
+        settings.buffer_length = (data.len() - trimmed) as i64;
+        settings.clamp();
+        // The whole file analyses cover the data up to the buffer length
+        settings.entropy_timeline.entropies.clear();
+        self.entropy_pass = EntropyPass::default();
+    }
+
+    /// Compute the entropy timeline of the whole file, once it is shown and the file is loaded
+    pub fn update_entropy_timeline(&mut self) {
+        let settings = &self.settings;
+        let timeline = &settings.entropy_timeline;
+        if !timeline.visible || !timeline.entropies.is_empty() || settings.loading.is_some() {
+            return;
+        }
+        self.settings.entropy_timeline.entropies =
+            analysis::entropy_timeline(self.data(), ENTROPY_TIMELINE_LENGTH);
+    }
+
+    /// Jump to the block with the highest or lowest entropy in the file. The blocks are scanned
+    /// in chunks, one per frame, and the result is kept for later jumps.
+    pub fn update_entropy_jump(&mut self) {
+        if let Some(jump) = self.settings.entropy_jump_requested.take() {
+            self.pending_entropy_jump = Some(jump);
+        }
+        let jump = match self.pending_entropy_jump {
+            Some(jump) => jump,
+            None => return,
+        };
+
+        // Borrowed from the buffer only, which leaves the pass to be advanced
+        let data = &self.buffer.data()[..self.data().len()];
+        if !self.entropy_pass.is_done(data) && !self.entropy_pass.advance(data) {
+            self.settings.analysis_progress = Some(self.entropy_pass.progress(data));
+            return;
+        }
+        self.settings.analysis_progress = None;
+        self.pending_entropy_jump = None;
+
+        let best = match jump {
+            EntropyJump::Highest => self.entropy_pass.highest,
+            EntropyJump::Lowest => self.entropy_pass.lowest,
+        };
+        if let Some((offset, entropy)) = best {
+            self.settings.offset = offset;
+            self.settings.offset_fine = 0;
+            self.settings.clamp();
+            self.settings.status = format!(
+                "Entropy {:.2} bits per byte at {}",
+                entropy * 8.0,
+                self.settings.display_offset(offset)
+            );
+        }
+    }
+
+    /// Open the file in the external hex editor, at the offset under the cursor or else at the
+    /// start of the view
+    pub fn update_editor(&mut self) {
+        let settings = &mut self.settings;
+        if !std::mem::take(&mut settings.editor_requested) {
+            return;
+        }
+
+        let path = match &self.path {
+            Some(path) => path,
+            None => {
+                settings.status = format!("{} is not a file to open", self.filename);
+                return;
+            }
+        };
+        let offset = settings
+            .cursor_grid_position()
+            .map(|grid| settings.offset_at(&grid))
+            .unwrap_or_else(|| settings.view_start());
+        settings.status = match editor::spawn(&settings.editor_command, path, offset) {
+            Ok(()) => format!("Opened the editor at {}", settings.display_offset(offset)),
+            Err(e) => format!(
+                "Could not start the editor '{}': {}",
+                settings.editor_command, e
+            ),
+        };
+    }
+
+    /// Run a requested search, or jump to the next match (wrapping around at the end)
+    pub fn update_search(&mut self) {
+        if self.settings.search.search_requested {
+            let found = search::parse_pattern(&self.settings.search.query).map(|pattern| {
+                let matches = search::find_all(self.data(), &pattern, MAX_SEARCH_MATCHES);
+                (pattern, matches)
+            });
+            let search = &mut self.settings.search;
+            search.search_requested = false;
+            search.current = None;
+            match found {
+                Ok((pattern, matches)) => {
+                    search.matches = matches;
+                    search.pattern_length = pattern.len() as i64;
+                    search.status = if search.matches.len() >= MAX_SEARCH_MATCHES {
+                        format!(
+                            "more than {} matches, showing the first",
+                            MAX_SEARCH_MATCHES
+                        )
+                    } else {
+                        format!("{} matches", search.matches.len())
+                    };
+                    search.next_requested = !search.matches.is_empty();
+                }
+                Err(e) => {
+                    search.matches.clear();
+                    search.status = e;
+                }
+            }
+        }
+
+        let search = &mut self.settings.search;
+        if search.next_requested {
+            search.next_requested = false;
+            if search.matches.is_empty() {
+                return;
+            }
+            let next = search.current.map_or(0, |i| (i + 1) % search.matches.len());
+            search.current = Some(next);
+            self.settings.offset = search.matches[next];
+            self.settings.offset_fine = 0;
+            self.settings.clamp();
+        }
+    }
+
+    pub fn update_column_profile(&mut self) {
+        let settings = &self.settings;
+        if !settings.column_profile.visible {
+            return;
+        }
+
+        let view = View::new(self.data(), settings.view_start(), settings.stride);
+        let width = settings.width;
+        let rows = settings.visible_rows();
+
+        let mut sums = vec![(0u32, 0f64, 0f64); width as usize];
+        for row in 0..rows {
+            for (column, (count, sum, sum_of_squares)) in sums.iter_mut().enumerate() {
+                if let Some(b) = view.byte_at(row * width + column as isize) {
+                    *count += 1;
+                    *sum += b as f64;
+                    *sum_of_squares += (b as f64) * (b as f64);
+                }
+            }
+        }
+        let deviation = sums
+            .iter()
+            .map(|&(count, sum, sum_of_squares)| {
+                if count == 0 {
+                    0.0
+                } else {
+                    let mean = sum / count as f64;
+                    (sum_of_squares / count as f64 - mean * mean)
+                        .max(0.0)
+                        .sqrt() as f32
+                }
+            })
+            .collect();
+
+        let selected_values = match settings.column_profile.selected_column {
+            Some(column) if column < width => (0..rows)
+                .map_while(|row| view.byte_at(row * width + column))
+                .collect(),
+            _ => vec![],
+        };
+
+        let column_profile = &mut self.settings.column_profile;
+        column_profile.deviation = deviation;
+        column_profile.selected_values = selected_values;
+    }
+
+    /// Find the structures to label in the visible part of the file, if it changed
+    pub fn update_annotations(&mut self) {
+        let settings = &self.settings;
+        let annotations = &settings.annotations;
+        if !annotations.signatures && !annotations.strings {
+            self.settings.annotations.annotations.clear();
+            self.settings.annotations.source = None;
+            return;
+        }
+
+        // Zoomed out or with a large stride, only the start of the view is searched
+        let start = settings.view_start().max(0);
+        let end = (settings.view_start() + settings.visible_rows() as i64 * settings.row_length())
+            .min(start + MAX_ANNOTATION_LENGTH);
+        let source = Some((start, end, annotations.signatures, annotations.strings));
+        if annotations.source == source {
+            return;
+        }
+
+        let found = annotation::find(
+            self.data(),
+            start as usize..end.max(start) as usize,
+            annotations.signatures,
+            annotations.strings,
+        );
+        let annotations = &mut self.settings.annotations;
+        annotations.annotations = found;
+        annotations.source = source;
+    }
+
+    /// Collect the values of the bytes of the visible records, which start at multiples of the
+    /// record size
+    pub fn update_sparklines(&mut self) {
+        let settings = &mut self.settings;
+        if !(settings.sparklines.visible && settings.record.enabled) {
+            settings.sparklines.values.clear();
+            return;
+        }
+
+        let size = settings.record.size as i64;
+        let view_start = settings.view_start();
+        let view_end = (view_start + settings.page_length()).min(settings.buffer_length);
+        let first = (view_start + size - 1).div_euclid(size).max(0);
+        let last = (view_end / size).min(first + MAX_SPARKLINE_RECORDS);
+
+        let data = self.buffer.data();
+        settings.sparklines.values = (0..size.min(MAX_SPARKLINES as i64))
+            .map(|byte| {
+                (first..last)
+                    .filter_map(|record| data.get((record * size + byte) as usize).copied())
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Read the bytes of the row under the mouse cursor, following the grid like the hover info
+    pub fn update_row_graph(&mut self) {
+        let settings = &self.settings;
+        let grid = match settings.cursor_grid_position() {
+            Some(grid) if settings.row_graph.visible => grid,
+            _ => return,
+        };
+
+        let pixels_per_byte = settings.pixels_per_byte();
+        let data = self.data();
+        let row: Vec<i64> = (0..settings.width / pixels_per_byte)
+            .map(|column| {
+                settings.offset_at(&GridPosition {
+                    column: column * pixels_per_byte,
+                    ..grid
+                })
+            })
+            .collect();
+        let values = row
+            .iter()
+            .map_while(|&offset| {
+                usize::try_from(offset)
+                    .ok()
+                    .and_then(|offset| data.get(offset))
+                    .copied()
+            })
+            .collect();
+
+        let row_graph = &mut self.settings.row_graph;
+        row_graph.offset = row[0];
+        row_graph.values = values;
+        row_graph.cursor_column = (grid.column / pixels_per_byte) as usize;
+    }
+
+    /// Find the range of byte values in the view for the contrast stretch, unless it is locked
+    pub fn update_contrast(&mut self) {
         let settings = &self.settings;
+        if !settings.contrast.enabled || settings.contrast.locked {
+            return;
+        }
+
+        let bit_mask = settings
+            .bit_mask
+            .enabled
+            .then(|| settings.bit_mask.byte_map());
+        let transformed;
+        let view = if settings.is_transformed() {
+            transformed = self.transformed_data(settings.page_length());
+            View::new(&transformed, 0, settings.stride)
+        } else {
+            View::new(self.data(), settings.view_start(), settings.stride)
+        };
+        let range = (0..settings.page_length() / settings.stride as i64)
+            .map_while(|i| view.byte_at(i as isize))
+            .map(|b| bit_mask.map_or(b, |bit_mask| bit_mask[b as usize]))
+            .fold(None, |range: Option<(u8, u8)>, b| {
+                Some(range.map_or((b, b), |(min, max)| (min.min(b), max.max(b))))
+            });
 
-        let view = View::new(
-            self.buffer.data(),
-            settings.offset + settings.offset_fine,
+        if let Some((min, max)) = range {
+            let contrast = &mut self.settings.contrast;
+            contrast.min = min;
+            contrast.max = max;
+        }
+    }
+
+    /// Mapping of the byte values by the bit mask and then the contrast stretch, if any of them
+    /// is enabled
+    fn byte_map(&self) -> Option<[u8; 256]> {
+        let settings = &self.settings;
+        let bit_mask = settings
+            .bit_mask
+            .enabled
+            .then(|| settings.bit_mask.byte_map());
+        if !settings.contrast.enabled {
+            return bit_mask;
+        }
+        let contrast = settings.contrast.byte_map();
+        Some(match bit_mask {
+            Some(bit_mask) => bit_mask.map(|b| contrast[b as usize]),
+            None => contrast,
+        })
+    }
+
+    /// Compute the spectrum of the visible bytes, if they changed
+    pub fn update_spectrum(&mut self) {
+        let settings = &self.settings;
+        if !settings.spectrum.visible {
+            return;
+        }
+
+        let view = View::new(self.data(), settings.view_start(), settings.stride);
+        let columns = settings.width / settings.pixels_per_byte();
+        let len = (settings.visible_rows() * columns).min(MAX_SPECTRUM_LENGTH);
+        let values: Vec<u8> = (0..len).map_while(|i| view.byte_at(i)).collect();
+
+        let source = Some((settings.view_start(), settings.stride, values.len()));
+        if settings.spectrum.source == source {
+            return;
+        }
+
+        let magnitudes = spectrum::magnitudes(&values);
+        let max_period = (settings.max_width() / settings.pixels_per_byte()) as f64;
+
+        let spectrum = &mut self.settings.spectrum;
+        spectrum.period = spectrum::strongest_period(&magnitudes, values.len(), max_period);
+        spectrum.magnitudes = magnitudes;
+        spectrum.len = values.len();
+        spectrum.source = source;
+    }
+
+    /// Rank the candidate widths by the coherence of the columns of the visible bytes, if they
+    /// or the range of widths changed
+    pub fn update_width_scores(&mut self) {
+        let settings = &self.settings;
+        let scores = &settings.width_scores;
+        if !scores.visible {
+            return;
+        }
+
+        let view = View::new(self.data(), settings.view_start(), settings.stride);
+        let columns = settings.width / settings.pixels_per_byte();
+        let len = (settings.visible_rows() * columns).min(MAX_WIDTH_SCORE_LENGTH);
+        let values: Vec<u8> = (0..len).map_while(|i| view.byte_at(i)).collect();
+
+        let source = Some((
+            settings.view_start(),
             settings.stride,
+            values.len(),
+            scores.min_width,
+            scores.max_width,
+        ));
+        if scores.source == source {
+            return;
+        }
+
+        let candidates = alignment::rank_widths(
+            &values,
+            scores.min_width as usize,
+            scores.max_width as usize,
+            WIDTH_CANDIDATES,
         );
+        let scores = &mut self.settings.width_scores;
+        scores.candidates = candidates
+            .into_iter()
+            .map(|(width, score)| (width as isize, score))
+            .collect();
+        scores.source = source;
+    }
 
-        let mut style: Box<dyn Style> = match settings.pixel_style {
-            PixelStyle::Colorful => Box::new(Colorful {}),
-            PixelStyle::Grayscale => Box::new(Grayscale {}),
-            PixelStyle::Category => Box::new(Category {}),
-            PixelStyle::GradientMagma => Box::new(ColorGradient::new(colorgrad::magma())),
-            PixelStyle::GradientPlasma => Box::new(ColorGradient::new(colorgrad::plasma())),
-            PixelStyle::GradientViridis => Box::new(ColorGradient::new(colorgrad::viridis())),
-            PixelStyle::GradientRainbow => Box::new(ColorGradient::new(colorgrad::rainbow())),
-            PixelStyle::GradientTurbo => Box::new(ColorGradient::new(colorgrad::turbo())),
-            PixelStyle::GradientCubehelix => {
-                Box::new(ColorGradient::new(colorgrad::cubehelix_default()))
-            }
-            PixelStyle::Rgba => Box::new(Rgba {}),
-            PixelStyle::Abgr => Box::new(Abgr {}),
+    /// Add the selection as a region, or write the regions to the sidecar file, if requested
+    pub fn update_regions(&mut self) {
+        let regions = &mut self.settings.regions;
+        if std::mem::take(&mut regions.add_requested) {
+            if let Some(selection) = self.settings.selection {
+                let range = selection.range();
+                regions.map.regions.push(Region {
+                    start: range.start,
+                    end: range.end,
+                    label: regions.label.clone(),
+                    color: regions.color,
+                });
+                regions.visible = true;
+                regions.modified = true;
+            }
+        }
+
+        if std::mem::take(&mut regions.save_requested) {
+            self.settings.status = match &self.path {
+                Some(path) => match regions.map.save(path) {
+                    Ok(sidecar_path) => {
+                        regions.modified = false;
+                        format!("Saved the regions to {}", sidecar_path.display())
+                    }
+                    Err(e) => format!("{:#}", e),
+                },
+                None => format!(
+                    "{} is not a file to save the regions next to",
+                    self.filename
+                ),
+            };
+        }
+    }
+
+    /// Measure how much the visible rows correlate, which is cheap enough for every frame
+    pub fn update_row_correlation(&mut self) {
+        let settings = &self.settings;
+        let view = View::new(self.data(), settings.view_start(), settings.stride);
+        let columns = settings.width / settings.pixels_per_byte();
+        let len = (settings.visible_rows() * columns).min(MAX_CORRELATION_LENGTH);
+        let values: Vec<u8> = (0..len).map_while(|i| view.byte_at(i)).collect();
+        self.settings.row_correlation = alignment::row_correlation(&values, columns as usize);
+    }
+
+    /// Compute the colors of the color scale, if the style or its settings changed
+    pub fn update_color_scale(&mut self) {
+        let settings = &self.settings;
+        let source = settings
+            .pixel_style
+            .is_single_byte()
+            .then(|| (settings.pixel_style, settings.style_settings.clone()));
+        if settings.color_scale.source == source {
+            return;
+        }
+
+        let colors = if source.is_some() {
+            self.byte_colors()
+        } else {
+            vec![]
+        };
+        self.settings.color_scale = ColorScale { colors, source };
+    }
+
+    /// Compute the run length levels of the buffer once the run length style is selected, unless
+    /// the buffer is too large to keep them
+    pub fn update_run_length_levels(&mut self) {
+        if self.settings.pixel_style == PixelStyle::RunLength
+            && self.run_length_levels.is_none()
+            && self.buffer.len() <= MAX_RUN_LENGTH_CACHE_SIZE
+        {
+            self.run_length_levels = Some(run_length_levels(self.buffer.data()));
+        }
+    }
+
+    /// The given style with the current settings, rendering views on 'data'
+    fn style<'a>(&'a self, pixel_style: PixelStyle, data: &'a [u8]) -> Box<dyn Style + 'a> {
+        let settings = &self.settings;
+        let gradient = |gradient| -> Box<dyn Style + 'a> {
+            let gradient = ColorGradient::new(gradient);
+            if settings.style_settings.reverse_gradient {
+                Box::new(gradient.reversed())
+            } else {
+                Box::new(gradient)
+            }
+        };
+
+        match pixel_style {
+            PixelStyle::Colorful => Box::new(Colorful {
+                palette: settings.style_settings.palette,
+            }),
+            PixelStyle::Grayscale => Box::new(Grayscale::with_gamma(
+                settings.style_settings.grayscale_gamma,
+            )),
+            PixelStyle::Category => Box::new(Category::new(
+                settings.style_settings.palette,
+                &settings.style_settings.category_rules,
+                settings.style_settings.category_shade_other,
+            )),
+            PixelStyle::GradientMagma => gradient(colorgrad::magma()),
+            PixelStyle::GradientPlasma => gradient(colorgrad::plasma()),
+            PixelStyle::GradientViridis => gradient(colorgrad::viridis()),
+            PixelStyle::GradientRainbow => gradient(colorgrad::rainbow()),
+            PixelStyle::GradientTurbo => gradient(colorgrad::turbo()),
+            PixelStyle::GradientCubehelix => gradient(colorgrad::cubehelix_default()),
+            PixelStyle::Rgba => Box::new(Rgba {
+                alpha_mode: settings.style_settings.alpha_mode,
+                width: settings.width,
+            }),
+            PixelStyle::Abgr => Box::new(Abgr {
+                alpha_mode: settings.style_settings.alpha_mode,
+                width: settings.width,
+            }),
             PixelStyle::Rgb => Box::new(Rgb {}),
             PixelStyle::Bgr => Box::new(Bgr {}),
             PixelStyle::Entropy => Box::new(Entropy::with_window_size(32)),
+            PixelStyle::Delta => Box::new(Delta::new(colorgrad::rd_bu())),
+            PixelStyle::Position => Box::new(Position::new(colorgrad::turbo())),
+            PixelStyle::Relief => Box::new(Relief::new(colorgrad::viridis(), settings.width)),
+            PixelStyle::RunLength => match &self.run_length_levels {
+                // Only the levels of the whole buffer are cached, those of other data (e.g. when
+                // folding) and of large buffers are computed for the visible bytes
+                Some(levels) if data.as_ptr() == self.buffer.data().as_ptr() => Box::new(
+                    RunLength::new(Cow::Borrowed(&levels[..]), colorgrad::inferno()),
+                ),
+                _ => Box::new(RunLength::visible(colorgrad::inferno())),
+            },
             PixelStyle::Datatype => Box::new(DatatypeStyle::new(
                 match (
-                    &settings.datatype_settings.datatype,
-                    settings.datatype_settings.signedness,
+                    &settings.style_settings.datatype.datatype,
+                    settings.style_settings.datatype.signedness,
                 ) {
                     (GuiDatatype::Integer8, signedness) => Datatype::Integer8(signedness),
                     (GuiDatatype::Integer16, signedness) => Datatype::Integer16(signedness),
@@ -111,26 +1315,954 @@ impl Binocle {
                     (GuiDatatype::Float32, _) => Datatype::Float32,
                     (GuiDatatype::Float64, _) => Datatype::Float64,
                 },
-                settings.datatype_settings.endianness,
-                settings.value_range,
+                settings.style_settings.datatype.endianness,
+                settings.style_settings.datatype.value_range,
+                settings.style_settings.datatype.diverging,
             )),
+            PixelStyle::Pointer => {
+                let pointer = &settings.style_settings.pointer;
+                let size = if pointer.size == 0 {
+                    settings.buffer_length as u64
+                } else {
+                    pointer.size
+                };
+                Box::new(Pointer::new(
+                    pointer.word_size,
+                    pointer.endianness,
+                    pointer.base,
+                    pointer.base.saturating_add(size),
+                ))
+            }
+        }
+    }
+
+    /// Draw into the frame of the (on-screen) canvas
+    pub fn draw(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        if settings.profile.enabled {
+            // The overlays refer to positions on the grid, which the profile does not have
+            self.draw_profile(frame);
+            self.draw_crosshair(frame);
+            return;
+        }
+        if settings.compare.enabled {
+            self.draw_tiles(frame);
+        } else {
+            self.draw_canvas(frame, settings.canvas_width, 1, settings.pixel_style);
+            self.draw_overlay(frame);
+            self.draw_mirror(frame);
+        }
+        self.draw_stride_hatch(frame);
+        self.draw_search_matches(frame);
+        self.draw_regions(frame);
+        self.draw_selection(frame);
+        self.draw_annotations(frame);
+        self.draw_pinned_row(frame);
+        self.draw_crosshair(frame);
+        self.draw_limit_indicators(frame);
+    }
+
+    /// Render the current view into a new RGBA buffer of the given size, independent of the
+    /// window. Every byte covers 'scale' times as many pixels as on screen.
+    pub fn render(&self, width: u32, height: u32, scale: u32) -> Image {
+        let mut image = Image::new(width, height);
+        self.draw_canvas(
+            &mut image.pixels,
+            width as isize,
+            scale as isize,
+            self.settings.pixel_style,
+        );
+        image
+    }
+
+    /// Append a footer describing the rendered data to an exported image
+    fn add_caption(&self, image: &Image, scale: u32) -> Image {
+        let settings = &self.settings;
+        let padding = 4 * scale;
+        let line_height = Image::line_height(scale);
+        let swatch_height = 8 * scale;
+
+        let rows = image.height as i64 / (settings.zoom_factor() as i64 * scale as i64);
+        let start = settings.view_start();
+        let end = (start + rows * settings.row_length()).min(settings.buffer_length);
+
+        let filename = Path::new(&self.filename)
+            .file_name()
+            .map(|f| f.to_string_lossy())
+            .unwrap_or_else(|| self.filename.as_str().into());
+        let lines = [
+            format!("{}  style: {:?}", filename, settings.pixel_style),
+            if settings.show_addresses {
+                format!(
+                    "address: 0x{:x} - 0x{:x}  width: {}  stride: {}",
+                    settings.address_base.wrapping_add(start),
+                    settings.address_base.wrapping_add(end),
+                    settings.width,
+                    settings.stride
+                )
+            } else {
+                format!(
+                    "offset: 0x{:x} - 0x{:x}  width: {}  stride: {}",
+                    start, end, settings.width, settings.stride
+                )
+            },
+        ];
+
+        let footer_height = 2 * padding + (lines.len() as u32) * line_height + swatch_height;
+        let mut image = image.with_footer(footer_height, [0, 0, 0, 255]);
+
+        let mut y = image.height - footer_height + padding;
+        for line in &lines {
+            image.draw_text((padding, y), line, scale, [255, 255, 255, 255]);
+            y += line_height;
+        }
+
+        // The palette swatch shows the color of every byte value, from 0x00 to 0xff
+        let colors = self.byte_colors();
+        let swatch_width = (image.width.saturating_sub(2 * padding)).min(256 * scale);
+        for x in 0..swatch_width {
+            let color = colors[(x * 256 / swatch_width) as usize];
+            image.fill_rect((padding + x, y), (1, swatch_height), color);
+        }
+
+        image
+    }
+
+    /// Colors of the byte values 0x00 to 0xff in the current style, as drawn for a ramp of all
+    /// bytes (only exact for the single byte styles)
+    fn byte_colors(&self) -> Vec<Color> {
+        let ramp: Vec<u8> = (0..=255).collect();
+        let view = View::new(&ramp, 0, 1);
+        let mut style = self.style(self.settings.pixel_style, &ramp);
+        style.init(std::slice::from_ref(&view), 256);
+        (0..256).map(|i| style.color_at_index(&view, i)).collect()
+    }
+
+    /// The selected interleaved stream of the data, starting at the current view, and with
+    /// 'copies' consecutive periods of it combined when folding. Only the first 'max_len' bytes
+    /// of the result are computed.
+    fn transformed_data(&self, max_len: i64) -> Vec<u8> {
+        let fold = &self.settings.fold;
+        let interleave = &self.settings.interleave;
+        let data = self.data();
+        let start = self.settings.view_start();
+
+        let byte_at = |index: i64| -> Option<u8> {
+            let position = start + interleave.source_index(index);
+            usize::try_from(self.settings.file_offset(position))
+                .ok()
+                .and_then(|index| data.get(index))
+                .copied()
         };
-        style.init(&view);
 
-        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-            let zoom_factor = settings.zoom_factor();
-            let x = ((i as isize) % WIDTH as isize) / zoom_factor;
-            let y = ((i as isize) / WIDTH as isize) / zoom_factor;
+        if !fold.enabled {
+            return (0..max_len).map_while(byte_at).collect();
+        }
+
+        (0..fold.period.min(max_len))
+            .map_while(|j| {
+                let first = byte_at(j)?;
+                let copies = (1..fold.copies).map_while(|k| byte_at(j + k * fold.period));
+                Some(match fold.mode {
+                    FoldMode::Xor => copies.fold(0, |acc, b| acc | (b ^ first)),
+                    FoldMode::Max => copies.fold(first, |acc, b| acc.max(b)),
+                })
+            })
+            .collect()
+    }
+
+    /// Position in the file (normalized to the range 0..1) of the byte shown at each of the first
+    /// 'len' indices of the pane, following the transforms, the stride and the nibbles. Indices
+    /// refer to 'transformed_data' of length 'transformed_len' while the view is transformed.
+    fn file_positions(&self, pane: usize, len: isize, transformed_len: usize) -> Vec<Option<f64>> {
+        let settings = &self.settings;
+        let transformed = settings.is_transformed();
+        let (start, data_len) = if transformed {
+            (0, transformed_len as i64)
+        } else {
+            (settings.view_start(), settings.buffer_length)
+        };
+        let pane_start = start + pane as i64 * settings.split_delta;
+
+        (0..len)
+            .map(|view_index| {
+                let byte_index = (view_index / settings.pixels_per_byte()) as i64;
+                let mut index = pane_start + byte_index * settings.stride as i64;
+                if settings.tile_data && data_len > 0 {
+                    index = index.rem_euclid(data_len);
+                }
+                if !(0..data_len).contains(&index) {
+                    return None;
+                }
+                let offset = if transformed {
+                    let position = settings.view_start() + settings.interleave.source_index(index);
+                    settings.file_offset(position)
+                } else {
+                    index
+                };
+                (0..settings.buffer_length)
+                    .contains(&offset)
+                    .then(|| offset as f64 / settings.buffer_length as f64)
+            })
+            .collect()
+    }
+
+    /// Draw the same view in each of the styles to compare, in tiles of the canvas
+    fn draw_tiles(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let (columns, _) = settings.compare.layout();
+        let (tile_width, tile_height) = settings.tile_size();
+
+        frame.fill(0);
+        let mut tile = vec![0; (tile_width * tile_height * 4) as usize];
+        for (i, &pixel_style) in settings.compare.styles().iter().enumerate() {
+            self.draw_canvas(&mut tile, tile_width, 1, pixel_style);
+
+            let left = (i as isize % columns) * tile_width;
+            let top = (i as isize / columns) * tile_height;
+            for (y, tile_row) in tile.chunks_exact((tile_width * 4) as usize).enumerate() {
+                let start = (((top + y as isize) * settings.canvas_width + left) * 4) as usize;
+                frame[start..start + tile_row.len()].copy_from_slice(tile_row);
+            }
+        }
+    }
+
+    fn draw_canvas(
+        &self,
+        frame: &mut [u8],
+        canvas_width: isize,
+        scale: isize,
+        pixel_style: PixelStyle,
+    ) {
+        let settings = &self.settings;
+        let pixel_size = settings.zoom_factor() * scale;
+
+        let transformed;
+        let (data, start) = if settings.is_transformed() {
+            let rows = (frame.len() as isize / 4 / canvas_width) / pixel_size + 1;
+            transformed = self.transformed_data(rows as i64 * settings.row_length());
+            (&transformed[..], 0)
+        } else {
+            (self.data(), settings.view_start())
+        };
+
+        // In split view, the right pane shows the data starting at 'split_delta' bytes after the
+        // start of the left pane.
+        let mut views = [
+            View::new(data, start, settings.stride),
+            View::new(data, start + settings.split_delta, settings.stride),
+        ];
+        if settings.tile_data {
+            views = views.map(View::wrapping);
+        }
+
+        // When the stride combines the bytes it steps over, the styles work on a copy of the
+        // visible data with one combined byte per step
+        let sampled;
+        let mut style_data = data;
+        let sampling = settings.stride > 1 && settings.stride_sampling != StrideSampling::Skip;
+        if sampling {
+            let rows = (frame.len() as isize / 4 / canvas_width) / pixel_size + 1;
+            let len = rows * settings.width;
+            let [left, right] = [start, start + settings.split_delta].map(|start| {
+                let view = View::new(data, start, 1);
+                if settings.tile_data {
+                    view.wrapping()
+                } else {
+                    view
+                }
+            });
+            sampled = [
+                sampled_data(&left, settings.stride, len, settings.stride_sampling),
+                sampled_data(&right, settings.stride, len, settings.stride_sampling),
+            ];
+            views = [View::new(&sampled[0], 0, 1), View::new(&sampled[1], 0, 1)];
+            style_data = &sampled[0];
+        }
+
+        // In nibble mode, the styles work on a copy of the visible data with one byte per nibble
+        let nibbles;
+        if settings.nibble_mode != NibbleMode::Off {
+            let rows = (frame.len() as isize / 4 / canvas_width) / pixel_size + 1;
+            let len = rows * settings.width;
+            let low_first = settings.nibble_mode == NibbleMode::LowFirst;
+            nibbles = [
+                nibble_data(&views[0], len, low_first),
+                nibble_data(&views[1], len, low_first),
+            ];
+            views = [View::new(&nibbles[0], 0, 1), View::new(&nibbles[1], 0, 1)];
+            style_data = &nibbles[0];
+        }
+
+        // The position style colors the bytes of copies by where they are in the file, such that
+        // moved data stands out
+        let positions;
+        let copied =
+            settings.is_transformed() || sampling || settings.nibble_mode != NibbleMode::Off;
+        if pixel_style == PixelStyle::Position && copied {
+            let rows = (frame.len() as isize / 4 / canvas_width) / pixel_size + 1;
+            let len = rows * settings.width;
+            positions = [0, 1].map(|pane| self.file_positions(pane, len, data.len()));
+            let [left, right] = views;
+            views = [
+                left.with_positions(&positions[0]),
+                right.with_positions(&positions[1]),
+            ];
+        }
+
+        let byte_map = self.byte_map();
+        if let Some(byte_map) = &byte_map {
+            views = views.map(|view| view.with_byte_map(byte_map));
+        }
+
+        let mut style = self.style(pixel_style, style_data);
+        let rows = (frame.len() as isize / 4 / canvas_width) / pixel_size + 1;
+        style.init(&views, rows * settings.width);
+
+        // Holes are shown like the area outside of the data, as long as the views index the file
+        let show_holes = settings.show_holes
+            && !self.holes.is_empty()
+            && !settings.is_transformed()
+            && !sampling
+            && settings.nibble_mode == NibbleMode::Off;
+        let text_overlay =
+            settings.text_overlay && !sampling && settings.nibble_mode == NibbleMode::Off;
+
+        let cell_color = |grid: &GridPosition| -> Color {
+            let view_index = grid.view_index(settings.width);
+            let in_hole = show_holes
+                && views[grid.pane]
+                    .data_index(view_index)
+                    .map_or(false, |i| buffer::is_in_hole(&self.holes, i as i64));
 
-            let color = if x >= settings.width {
+            let mut color = if grid.column >= settings.width || in_hole {
                 [0, 0, 0, 0]
             } else {
-                let view_index = y * settings.width + x;
+                style.color_at_index(&views[grid.pane], view_index)
+            };
 
-                style.color_at_index(&view, view_index)
+            if text_overlay && grid.column < settings.width {
+                let is_text = views[grid.pane]
+                    .data_index(view_index)
+                    .and_then(|i| data.get(i))
+                    .map_or(false, |&b| b.is_ascii_graphic() || b == b' ');
+                if is_text {
+                    color = tint(color, TEXT_OVERLAY_COLOR);
+                }
+            }
+            color
+        };
+
+        // Every grid row only depends on the data and the settings, so the rows are drawn in
+        // parallel. At high zoom levels, a grid cell covers many pixels: its color is computed
+        // once for the first line of the row, which is then copied to the other lines.
+        let line_length = canvas_width as usize * 4;
+        frame
+            .par_chunks_mut(line_length * pixel_size as usize)
+            .enumerate()
+            .for_each(|(row, band)| {
+                let (first_line, other_lines) = band.split_at_mut(line_length.min(band.len()));
+                let canvas_y = row as isize * pixel_size;
+                let mut previous: Option<(GridPosition, Color)> = None;
+                for (x, pixel) in first_line.chunks_exact_mut(4).enumerate() {
+                    let grid =
+                        settings.grid_position((x as isize, canvas_y), canvas_width, pixel_size);
+                    let color = match previous {
+                        Some((previous_grid, color)) if previous_grid == grid => color,
+                        _ => cell_color(&grid),
+                    };
+                    pixel.copy_from_slice(&color);
+                    previous = Some((grid, color));
+                }
+                for line in other_lines.chunks_mut(line_length) {
+                    line.copy_from_slice(&first_line[..line.len()]);
+                }
+            });
+    }
+
+    /// Blend the next version of the file, shifted by the overlay delta, over the canvas. This is
+    /// only done for the plain view of the bytes, without transforms or nibbles.
+    fn draw_overlay(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let overlay = &settings.overlay;
+        let version = match self.other_versions.first() {
+            Some(version)
+                if overlay.enabled
+                    && !settings.is_transformed()
+                    && settings.nibble_mode == NibbleMode::Off =>
+            {
+                version
+            }
+            _ => return,
+        };
+
+        let data = version.buffer.data();
+        let start = settings.view_start() + overlay.delta;
+        let views = [
+            View::new(data, start, settings.stride),
+            View::new(data, start + settings.split_delta, settings.stride),
+        ];
+        // Styles which depend on all of the data, like the run lengths, are only computed for the
+        // visible bytes of the version, instead of caching them for every version
+        let mut style = self.style(settings.pixel_style, data);
+        style.init(&views, (settings.visible_rows() + 1) * settings.width);
+
+        self.blend_views(frame, &views, &*style, overlay.alpha);
+    }
+
+    /// Blend the data mirrored around the mirror axis over the canvas, with the same restrictions
+    /// as the overlay of the next version. Styles which color a byte by its place in the data are
+    /// left out, as the mirrored bytes are a copy of the visible ones only.
+    fn draw_mirror(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let mirror = &settings.mirror;
+        if !mirror.enabled
+            || settings.is_transformed()
+            || settings.nibble_mode != NibbleMode::Off
+            || settings.pixel_style.is_positional()
+        {
+            return;
+        }
+
+        let data = self.data();
+        let len = (settings.visible_rows() as i64 + 1) * settings.row_length();
+        let start = settings.view_start();
+        let windows = [start, start + settings.split_delta]
+            .map(|start| mirrored_window(data, mirror.offset_sum() - start, len));
+        let views = [0, 1].map(|pane| {
+            let (window, skipped) = &windows[pane];
+            View::new(window, -skipped, settings.stride)
+        });
+        let mut style = self.style(settings.pixel_style, data);
+        style.init(&views, (settings.visible_rows() + 1) * settings.width);
+
+        self.blend_views(frame, &views, &*style, mirror.alpha);
+    }
+
+    /// Blend the colors of the views (one for each pane) over the grid cells of the canvas
+    fn blend_views(&self, frame: &mut [u8], views: &[View; 2], style: &dyn Style, alpha: f32) {
+        let settings = &self.settings;
+        let pixel_size = settings.zoom_factor();
+        let canvas_width = settings.canvas_width;
+        frame
+            .par_chunks_exact_mut(4)
+            .enumerate()
+            .for_each(|(i, pixel)| {
+                let canvas_pos = ((i as isize) % canvas_width, (i as isize) / canvas_width);
+                let grid = settings.grid_position(canvas_pos, canvas_width, pixel_size);
+                if grid.column >= settings.width {
+                    return;
+                }
+
+                let color =
+                    style.color_at_index(&views[grid.pane], grid.view_index(settings.width));
+                let alpha = alpha.clamp(0.0, 1.0) * color[3] as f32 / 255.0;
+                for (channel, blended) in pixel[..3].iter_mut().zip(color) {
+                    *channel =
+                        (*channel as f32 * (1.0 - alpha) + blended as f32 * alpha).round() as u8;
+                }
+                pixel[3] = pixel[3].max((alpha * 255.0).round() as u8);
+            });
+    }
+
+    /// Draw the bytes from the start of the view as a trace, with one byte per column of grid
+    /// cells and its value on the vertical axis
+    fn draw_profile(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let pixel_size = settings.zoom_factor();
+        let (width, height) = (settings.canvas_width, settings.canvas_height);
+
+        let byte_map = settings
+            .bit_mask
+            .enabled
+            .then(|| settings.bit_mask.byte_map());
+        let mut view = View::new(self.data(), settings.view_start(), settings.stride);
+        if let Some(byte_map) = &byte_map {
+            view = view.with_byte_map(byte_map);
+        }
+        let mut style = self.style(settings.pixel_style, self.data());
+        style.init(std::slice::from_ref(&view), width);
+
+        let signed = settings.profile.signed;
+        let y_of = |b: u8| {
+            let level = if signed {
+                b as i8 as isize + 128
+            } else {
+                b as isize
             };
+            (255 - level) * (height - 1) / 255
+        };
+        frame.fill(0);
+        let mut put_pixel = |x: isize, y: isize, color: [u8; 4]| {
+            let i = ((y * width + x) * 4) as usize;
+            frame[i..i + 4].copy_from_slice(&color);
+        };
+
+        let zero = y_of(0);
+        for x in 0..width {
+            put_pixel(x, zero, [64, 64, 64, 255]);
+        }
+
+        let mut previous = None;
+        for x in 0..width {
+            let index = x / pixel_size;
+            let y = match view.byte_at(index) {
+                Some(b) => y_of(b),
+                None => break,
+            };
+            let mut color = style.color_at_index(&view, index);
+            color[3] = 255;
+
+            // Connect to the previous value with a vertical line
+            let (top, bottom) = previous.map_or((y, y), |p: isize| (p.min(y), p.max(y)));
+            for y in top..=bottom {
+                put_pixel(x, y, color);
+            }
+            previous = Some(y);
+        }
+    }
+
+    /// Draw a one pixel wide crosshair with inverted colors through the cursor position
+    fn draw_crosshair(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let (cursor_x, cursor_y) = match settings.cursor {
+            Some(cursor) if settings.crosshair_visible => cursor,
+            _ => return,
+        };
+
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let x = (i as isize) % settings.canvas_width;
+            let y = (i as isize) / settings.canvas_width;
+            if x == cursor_x || y == cursor_y {
+                pixel.copy_from_slice(&[255 - pixel[0], 255 - pixel[1], 255 - pixel[2], 255]);
+            }
+        }
+    }
+
+    /// Darken diagonal lines across the canvas while the stride skips bytes, such that a sampled
+    /// view is not mistaken for the whole data
+    fn draw_stride_hatch(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        if !settings.stride_hatch || !settings.skips_bytes() {
+            return;
+        }
+
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let x = (i as isize) % settings.canvas_width;
+            let y = (i as isize) / settings.canvas_width;
+            if (x + y) % STRIDE_HATCH_SPACING == 0 {
+                for channel in &mut pixel[..3] {
+                    *channel = (*channel as u16 * STRIDE_HATCH_BRIGHTNESS / 256) as u8;
+                }
+            }
+        }
+    }
+
+    /// Highlight all bytes which are part of a search match, the current one in a different color
+    fn draw_search_matches(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let search = &settings.search;
+        if search.matches.is_empty() {
+            return;
+        }
 
+        let (tile_width, tile_height) = settings.tile_size();
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let x = (i as isize) % settings.canvas_width;
+            let y = (i as isize) / settings.canvas_width;
+            let grid = settings.grid_position(
+                (x % tile_width, y % tile_height),
+                tile_width,
+                settings.zoom_factor(),
+            );
+            if grid.column >= settings.width {
+                continue;
+            }
+
+            let color = match search.match_at(settings.offset_at(&grid)) {
+                Some(index) if Some(index) == search.current => [255, 128, 0],
+                Some(_) => [255, 255, 0],
+                None => continue,
+            };
+            let color = tint([pixel[0], pixel[1], pixel[2], pixel[3]], color);
             pixel.copy_from_slice(&color);
         }
     }
+
+    /// Tint the bytes of each labeled region in its color
+    fn draw_regions(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let regions = &settings.regions;
+        if !regions.visible || regions.map.regions.is_empty() {
+            return;
+        }
+
+        // The regions are looked up once per grid cell of each pane, as that scans all of them
+        let width = settings.width;
+        let cells = (settings.visible_rows() + 1) * width;
+        let cell_colors = [0, 1].map(|pane| {
+            (0..cells)
+                .into_par_iter()
+                .map(|view_index| {
+                    let grid = GridPosition {
+                        pane,
+                        column: view_index % width,
+                        row: view_index / width,
+                    };
+                    let region = regions.map.region_at(settings.offset_at(&grid));
+                    region.map(|region| region.color)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let (tile_width, tile_height) = settings.tile_size();
+        let canvas_width = settings.canvas_width;
+        frame
+            .par_chunks_exact_mut(4)
+            .enumerate()
+            .for_each(|(i, pixel)| {
+                let x = (i as isize) % canvas_width;
+                let y = (i as isize) / canvas_width;
+                let grid = settings.grid_position(
+                    (x % tile_width, y % tile_height),
+                    tile_width,
+                    settings.zoom_factor(),
+                );
+                if grid.column >= width {
+                    return;
+                }
+                let color = match cell_colors[grid.pane].get(grid.view_index(width) as usize) {
+                    Some(color) => *color,
+                    None => regions
+                        .map
+                        .region_at(settings.offset_at(&grid))
+                        .map(|region| region.color),
+                };
+                if let Some(color) = color {
+                    let tinted = tint([pixel[0], pixel[1], pixel[2], pixel[3]], color);
+                    pixel.copy_from_slice(&tinted);
+                }
+            });
+    }
+
+    /// Tint the selected bytes, wherever they are in the view
+    fn draw_selection(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let range = match settings.selection {
+            Some(selection) => selection.range(),
+            None => return,
+        };
+
+        let (tile_width, tile_height) = settings.tile_size();
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let x = (i as isize) % settings.canvas_width;
+            let y = (i as isize) / settings.canvas_width;
+            let grid = settings.grid_position(
+                (x % tile_width, y % tile_height),
+                tile_width,
+                settings.zoom_factor(),
+            );
+            if grid.column < settings.width && range.contains(&settings.offset_at(&grid)) {
+                let color = tint([pixel[0], pixel[1], pixel[2], pixel[3]], SELECTION_COLOR);
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Mark the annotated structures and label them, in the left pane of the first tile
+    fn draw_annotations(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        if settings.is_transformed() || settings.annotations.annotations.is_empty() {
+            return;
+        }
+
+        let char_width = (font::GLYPH_WIDTH + 1) as isize;
+        let label_height = font::GLYPH_HEIGHT as isize + 2;
+        let zoom_factor = settings.zoom_factor();
+        let visible: Vec<_> = settings
+            .annotations
+            .annotations
+            .iter()
+            .filter_map(|annotation| {
+                settings
+                    .canvas_position(annotation.offset)
+                    .map(|position| (annotation, position))
+            })
+            .collect();
+        let labels: Vec<_> = visible
+            .iter()
+            .map(|(annotation, (x, y))| {
+                let width = annotation.label.chars().count() as isize * char_width + 1;
+                ((x + zoom_factor + 2, *y), (width, label_height))
+            })
+            .collect();
+        let positions = annotation::place_labels(&labels, ANNOTATION_LABEL_STEPS);
+
+        let (canvas_width, canvas_height) = (settings.canvas_width, settings.canvas_height);
+        let fill_rect =
+            |frame: &mut [u8], (x, y): (isize, isize), (w, h): (isize, isize), color: [u8; 4]| {
+                for py in y.max(0)..(y + h).min(canvas_height) {
+                    for px in x.max(0)..(x + w).min(canvas_width) {
+                        let i = ((py * canvas_width + px) * 4) as usize;
+                        frame[i..i + 4].copy_from_slice(&color);
+                    }
+                }
+            };
+
+        for (((annotation, (x, y)), (_, size)), position) in
+            visible.iter().zip(&labels).zip(positions)
+        {
+            let color = match annotation.kind {
+                AnnotationKind::Signature => [230, 159, 0, 255],
+                AnnotationKind::String => [86, 180, 233, 255],
+            };
+            // Frame the cell of the byte
+            let marker_size = zoom_factor + 2;
+            fill_rect(frame, (x - 1, y - 1), (marker_size, 1), color);
+            fill_rect(frame, (x - 1, y + zoom_factor), (marker_size, 1), color);
+            fill_rect(frame, (x - 1, y - 1), (1, marker_size), color);
+            fill_rect(frame, (x + zoom_factor, y - 1), (1, marker_size), color);
+
+            if let Some((label_x, label_y)) = position {
+                fill_rect(frame, (label_x, label_y), *size, [0, 0, 0, 255]);
+                if label_x >= 0 && label_y >= 0 {
+                    font::draw_text(
+                        frame,
+                        canvas_width as u32,
+                        (label_x as u32 + 1, label_y as u32 + 1),
+                        &annotation.label,
+                        1,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Tint the top edge of the canvas at the beginning of the file and the bottom edge when the
+    /// end of the file is visible
+    fn draw_limit_indicators(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let row_bytes = (settings.canvas_width * 4) as usize;
+        let edge_bytes = LIMIT_INDICATOR_SIZE * row_bytes;
+        if frame.len() < 2 * edge_bytes {
+            return;
+        }
+
+        let view_end =
+            settings.view_start() + settings.visible_rows() as i64 * settings.row_length();
+        let len = frame.len();
+        let edges = [
+            (settings.view_start() <= 0, 0..edge_bytes, [64, 192, 255]),
+            (
+                view_end >= settings.buffer_length,
+                len - edge_bytes..len,
+                [255, 128, 64],
+            ),
+        ];
+
+        for (at_limit, range, tint) in edges {
+            if !at_limit {
+                continue;
+            }
+            for pixel in frame[range].chunks_exact_mut(4) {
+                let color = self::tint([pixel[0], pixel[1], pixel[2], pixel[3]], tint);
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Draw the pinned row over the top edge of the canvas, with a line below it
+    fn draw_pinned_row(&self, frame: &mut [u8]) {
+        let settings = &self.settings;
+        let pinned = match &settings.pinned_row {
+            Some(pinned) if settings.canvas_height > 0 => pinned,
+            _ => return,
+        };
+
+        let width = pinned.width.min(settings.canvas_width) as usize;
+        let height = pinned.height.min(settings.canvas_height - 1) as usize;
+        let frame_row_len = settings.canvas_width as usize * 4;
+        for y in 0..height {
+            let source = &pinned.pixels[y * pinned.width as usize * 4..][..width * 4];
+            frame[y * frame_row_len..][..width * 4].copy_from_slice(source);
+        }
+        for pixel in frame[height * frame_row_len..][..frame_row_len].chunks_exact_mut(4) {
+            pixel.copy_from_slice(&PINNED_ROW_SEPARATOR_COLOR);
+        }
+    }
+
+    /// Capture the row under the cursor (or the first row of the view) as the pinned row
+    pub fn update_pinned_row(&mut self) {
+        if !std::mem::take(&mut self.settings.pin_row_requested) {
+            return;
+        }
+
+        let settings = &self.settings;
+        let row = settings.cursor_grid_position().map_or(0, |grid| grid.row);
+        let zoom_factor = settings.zoom_factor();
+        let width = settings.canvas_width;
+        let image = self.render(width as u32, ((row + 1) * zoom_factor) as u32, 1);
+        let pixels = image.pixels[(row * zoom_factor * width * 4) as usize..].to_vec();
+        let offset =
+            settings.file_offset(settings.view_start() + row as i64 * settings.row_length());
+
+        self.settings.status = format!("Pinned the row at {}", settings.display_offset(offset));
+        self.settings.pinned_row = Some(PinnedRow {
+            offset,
+            width,
+            height: zoom_factor,
+            pixels,
+        });
+    }
+
+    /// Render the preview thumbnail or write the PNG file, if requested in the export dialog
+    pub fn update_export(&mut self) {
+        if self.settings.export.palette_requested {
+            let colors = self.byte_colors();
+            let export = &mut self.settings.export;
+            export.status = match export::save_palette(
+                &export.palette_path,
+                self.settings.pixel_style.name(),
+                &colors,
+            ) {
+                Ok(()) => format!("Saved {}", export.palette_path),
+                Err(e) => format!("Error: {:#}", e),
+            };
+            export.palette_requested = false;
+        }
+
+        let export = &self.settings.export;
+        if !(export.preview_requested || export.save_requested) {
+            return;
+        }
+
+        let mut image = self.render(export.width, export.height, export.scale);
+        if export.caption {
+            image = self.add_caption(&image, export.scale);
+        }
+
+        let export = &mut self.settings.export;
+        if export.preview_requested {
+            export.preview = Some(image.thumbnail(PREVIEW_SIZE));
+            export.preview_requested = false;
+        }
+        if export.save_requested {
+            export.status = match image.save_png(&export.path) {
+                Ok(()) => format!("Saved {}", export.path),
+                Err(e) => format!("Error: {:#}", e),
+            };
+            export.save_requested = false;
+        }
+    }
+}
+
+/// Up to 'len' bytes of the data backwards from offset 'first', together with the number of
+/// leading bytes that were skipped because they lie past the end of the data
+fn mirrored_window(data: &[u8], first: i64, len: i64) -> (Vec<u8>, i64) {
+    let skipped = (first - (data.len() as i64 - 1)).clamp(0, len.max(0));
+    let window = (skipped..len)
+        .map_while(|k| data.get(usize::try_from(first - k).ok()?).copied())
+        .collect();
+    (window, skipped)
+}
+
+#[test]
+fn mirrored_window_reverses_data() {
+    let data = [0, 1, 2, 3, 4];
+    assert_eq!(mirrored_window(&data, 3, 3), (vec![3, 2, 1], 0));
+    assert_eq!(mirrored_window(&data, 6, 4), (vec![4, 3], 2));
+    assert_eq!(mirrored_window(&data, 1, 4), (vec![1, 0], 0));
+    assert_eq!(mirrored_window(&data, -1, 4), (vec![], 0));
+}
+
+/// The first 'len' steps of 'stride' bytes of the view, each combined into a single byte
+fn sampled_data(view: &View, stride: isize, len: isize, sampling: StrideSampling) -> Vec<u8> {
+    (0..len)
+        .map_while(|i| {
+            let first = view.byte_at(i * stride)?;
+            let rest = (1..stride).map_while(|k| view.byte_at(i * stride + k));
+            Some(match sampling {
+                StrideSampling::Skip => first,
+                StrideSampling::Mean => {
+                    let (sum, count) = rest.fold((first as u32, 1), |(sum, count), b| {
+                        (sum + b as u32, count + 1)
+                    });
+                    ((sum + count / 2) / count) as u8
+                }
+                StrideSampling::Max => rest.fold(first, |acc, b| acc.max(b)),
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn sampled_data_combines_steps() {
+    let data = [0, 0, 0, 200, 10, 20, 30];
+    let view = View::new(&data, 0, 1);
+    let sample = |sampling| sampled_data(&view, 4, 3, sampling);
+    assert_eq!(sample(StrideSampling::Skip), vec![0, 10]);
+    assert_eq!(sample(StrideSampling::Mean), vec![50, 20]);
+    assert_eq!(sample(StrideSampling::Max), vec![200, 30]);
+}
+
+/// The first 'len' nibbles of the view, each scaled from 0–15 to 0–255
+fn nibble_data(view: &View, len: isize, low_first: bool) -> Vec<u8> {
+    (0..len)
+        .map_while(|i| {
+            let byte = view.byte_at(i / 2)?;
+            let nibble = if (i % 2 == 0) != low_first {
+                byte >> 4
+            } else {
+                byte & 0x0f
+            };
+            Some(nibble * 17)
+        })
+        .collect()
+}
+
+#[test]
+fn nibble_data_order() {
+    let data = [0x12, 0xf0];
+    let view = View::new(&data, 0, 1);
+    assert_eq!(nibble_data(&view, 4, false), vec![0x11, 0x22, 0xff, 0x00]);
+    assert_eq!(nibble_data(&view, 3, true), vec![0x22, 0x11, 0x00]);
+}
+
+#[test]
+fn trimmed_padding_is_left_out_of_the_data() {
+    let mut binocle = Binocle::from_bytes(vec![1, 0, 2, 0, 0, 0]);
+    binocle.settings.padding.enabled = true;
+    binocle.settings.padding.byte = 0;
+    binocle.update_padding();
+    assert_eq!(binocle.settings.padding.trimmed, 3);
+    assert_eq!(binocle.settings.buffer_length, 3);
+    assert_eq!(binocle.data(), [1, 0, 2]);
+
+    binocle.settings.search.query = "00".into();
+    binocle.settings.search.search_requested = true;
+    binocle.update_search();
+    assert_eq!(binocle.settings.search.matches, [1]);
+
+    binocle.settings.padding.enabled = false;
+    binocle.update_padding();
+    assert_eq!(binocle.settings.padding.trimmed, 0);
+    assert_eq!(binocle.data().len(), 6);
+}
+
+#[test]
+fn cancelled_download_is_dropped() {
+    let mut binocle = Binocle::from_bytes(vec![]);
+    binocle.pending_download = Some(Mutex::new(Box::new(std::io::empty())));
+    binocle.settings.loading = Some(LoadingState {
+        file_size: 0,
+        bytes_read: 0,
+        started: false,
+        choice: Some(LoadChoice::Cancel),
+        download: true,
+    });
+    binocle.update_loading();
+    assert!(binocle.settings.loading.is_none());
+    assert!(binocle.pending_download.is_none());
+    assert!(binocle.loader.is_none());
 }
@@ -1,6 +1,154 @@
-use memmap2::Mmap;
-use std::io::{BufReader, Read};
-use std::{fs::File, io, path::Path};
+use log::warn;
+use memmap2::{Mmap, MmapOptions};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::{
+    fs::{self, File},
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Decompressed sizes above this threshold trigger a warning about the memory usage
+const LARGE_DECOMPRESSED_SIZE: usize = 1 << 30;
+
+/// Devices without a known size (like '/dev/urandom') are read up to this many bytes
+const DEVICE_READ_LIMIT: u64 = 1 << 30;
+
+/// Size of the chunks in which devices are read
+const DEVICE_CHUNK_SIZE: usize = 1 << 20;
+
+/// Replace a leading '~' with the home directory
+fn expand_home(path: &str, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Absolute path of the file to open, with '~' expanded and symlinks resolved. The error names
+/// the absolute path, so that it is clear which file was looked for.
+pub fn resolve_path(path: &str) -> io::Result<PathBuf> {
+    let path = expand_home(path, dirs::home_dir().as_deref());
+    let absolute = if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    fs::canonicalize(&absolute).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Could not open '{}': {}", absolute.display(), e),
+        )
+    })
+}
+
+/// Whether the path refers to a block or character device instead of a regular file
+#[cfg(unix)]
+pub fn is_device<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = std::fs::metadata(path)?.file_type();
+    Ok(file_type.is_block_device() || file_type.is_char_device())
+}
+
+#[cfg(not(unix))]
+pub fn is_device<P: AsRef<Path>>(_path: P) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// Size of a block device as reported by the kernel
+#[cfg(target_os = "linux")]
+fn block_device_size(file: &File) -> Option<u64> {
+    use std::os::unix::io::AsRawFd;
+
+    // _IOR(0x12, 114, size_t), which is not exported by the libc crate
+    const BLKGETSIZE64: libc::c_ulong = (2 << 30)
+        | ((std::mem::size_of::<libc::size_t>() as libc::c_ulong) << 16)
+        | (0x12 << 8)
+        | 114;
+
+    let mut size: u64 = 0;
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), BLKGETSIZE64 as _, &mut size) };
+    (result == 0).then_some(size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn block_device_size(_file: &File) -> Option<u64> {
+    None
+}
+
+/// Ranges of a sparse file which are not backed by any data, found with SEEK_HOLE and SEEK_DATA
+#[cfg(target_os = "linux")]
+pub fn find_holes<P: AsRef<Path>>(path: P) -> io::Result<Vec<Range<i64>>> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = File::open(path)?;
+    let len = file.metadata()?.len() as i64;
+    let fd = file.as_raw_fd();
+
+    let mut holes = vec![];
+    let mut position = 0;
+    while position < len {
+        let start = unsafe { libc::lseek(fd, position as libc::off_t, libc::SEEK_HOLE) } as i64;
+        if start < 0 || start >= len {
+            break;
+        }
+        // Fails with ENXIO if there is no more data after the hole
+        let end = unsafe { libc::lseek(fd, start as libc::off_t, libc::SEEK_DATA) } as i64;
+        let end = if end < 0 { len } else { end };
+        holes.push(start..end);
+        position = end;
+    }
+    Ok(holes)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn find_holes<P: AsRef<Path>>(_path: P) -> io::Result<Vec<Range<i64>>> {
+    Ok(vec![])
+}
+
+/// Whether the offset is part of one of the sorted, non-overlapping holes
+pub fn is_in_hole(holes: &[Range<i64>], offset: i64) -> bool {
+    let i = holes.partition_point(|hole| hole.end <= offset);
+    holes.get(i).map_or(false, |hole| hole.contains(&offset))
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the magic bytes at the start of the file, or from
+    /// the file extension.
+    pub fn detect<P: AsRef<Path>>(path: P) -> io::Result<Option<Self>> {
+        let path = path.as_ref();
+
+        let mut magic = [0u8; 4];
+        let mut file = File::open(path)?;
+        let magic_length = file.read(&mut magic)?;
+        let magic = &magic[..magic_length];
+
+        let from_magic = if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        };
+
+        let from_extension = match path.extension().and_then(|e| e.to_str()) {
+            Some("gz" | "gzip") => Some(Compression::Gzip),
+            Some("zst" | "zstd") => Some(Compression::Zstd),
+            _ => None,
+        };
+
+        Ok(from_magic.or(from_extension))
+    }
+}
 
 pub struct MMapBacking {
     _file: File,
@@ -30,6 +178,71 @@ impl Buffer {
         Ok(Buffer::VecBuffer(data))
     }
 
+    pub fn from_compressed_file<P: AsRef<Path>>(
+        path: P,
+        compression: Compression,
+    ) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let mut reader: Box<dyn Read> = match compression {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+        };
+
+        let mut data: Vec<u8> = vec![];
+        reader.read_to_end(&mut data)?;
+
+        if data.len() > LARGE_DECOMPRESSED_SIZE {
+            warn!(
+                "The decompressed data occupies {} MiB of memory",
+                data.len() >> 20
+            );
+        }
+
+        Ok(Buffer::VecBuffer(data))
+    }
+
+    /// Devices do not report their size in the metadata and may never reach the end of the
+    /// file. Block devices are memory mapped with the size reported by the kernel (or found by
+    /// seeking to the end); all other devices are read in chunks up to a fixed limit.
+    pub fn from_device<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+
+        let size = block_device_size(&file).or_else(|| {
+            let size = file.seek(SeekFrom::End(0)).ok()?;
+            file.seek(SeekFrom::Start(0)).ok()?;
+            (size > 0).then_some(size)
+        });
+
+        if let Some(size) = size {
+            match usize::try_from(size)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+                .and_then(|len| unsafe { MmapOptions::new().len(len).map(&file) })
+            {
+                Ok(mmap) => return Ok(Buffer::MmapBuffer(MMapBacking::new(file, mmap))),
+                Err(e) => warn!("Could not map the device ({}), reading it instead", e),
+            }
+        }
+
+        let limit = size.unwrap_or(DEVICE_READ_LIMIT).min(DEVICE_READ_LIMIT);
+        if size.map_or(true, |size| size > limit) {
+            warn!("Only reading the first {} MiB of the device", limit >> 20);
+        }
+
+        let mut data = vec![];
+        let mut chunk = vec![0u8; DEVICE_CHUNK_SIZE];
+        while (data.len() as u64) < limit {
+            let max_len = (limit - data.len() as u64).min(DEVICE_CHUNK_SIZE as u64) as usize;
+            match file.read(&mut chunk[..max_len]) {
+                Ok(0) => break,
+                Ok(len) => data.extend_from_slice(&chunk[..len]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Buffer::VecBuffer(data))
+    }
+
     pub fn from_mmap<P: AsRef<Path>>(path: P) -> io::Result<Self> {
         let file = File::open(path)?;
         let mmap = unsafe { Mmap::map(&file)? };
@@ -43,6 +256,10 @@ impl Buffer {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn data(&self) -> &[u8] {
         match self {
             Buffer::VecBuffer(data) => data,
@@ -50,3 +267,25 @@ impl Buffer {
         }
     }
 }
+
+#[test]
+fn expand_home_directory() {
+    let home = Path::new("/home/user");
+    assert_eq!(expand_home("~", Some(home)), home);
+    assert_eq!(expand_home("~/a/b", Some(home)), home.join("a/b"));
+    assert_eq!(expand_home("~other/a", Some(home)), Path::new("~other/a"));
+    assert_eq!(expand_home("a/~", Some(home)), Path::new("a/~"));
+    assert_eq!(expand_home("~/a", None), Path::new("~/a"));
+}
+
+#[test]
+fn offsets_in_holes() {
+    let holes = [0..4, 10..20];
+    assert!(is_in_hole(&holes, 0));
+    assert!(is_in_hole(&holes, 3));
+    assert!(!is_in_hole(&holes, 4));
+    assert!(!is_in_hole(&holes, 9));
+    assert!(is_in_hole(&holes, 19));
+    assert!(!is_in_hole(&holes, 20));
+    assert!(!is_in_hole(&[], 0));
+}
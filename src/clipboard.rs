@@ -0,0 +1,83 @@
+//! Loading bytes from the clipboard and copying images to it
+
+use std::borrow::Cow;
+
+/// Bytes of a text from the clipboard: hexadecimal bytes like '0xde 0xad be,ef' (whitespace,
+/// commas and '0x' prefixes are ignored), or the raw bytes of the text if it contains control
+/// characters and is therefore not meant to be hex.
+pub fn decode(text: &str) -> Result<Vec<u8>, String> {
+    if text.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+        return Ok(text.as_bytes().to_vec());
+    }
+
+    let digits: String = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|token| {
+            token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+                .unwrap_or(token)
+        })
+        .collect();
+    if digits.is_empty() {
+        return Err("The clipboard is empty".into());
+    }
+    if digits.len() % 2 != 0 {
+        return Err("Odd number of hexadecimal digits".into());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            let pair = digits.get(i..i + 2).unwrap_or("");
+            u8::from_str_radix(pair, 16).map_err(|_| format!("Invalid hexadecimal byte '{}'", pair))
+        })
+        .collect()
+}
+
+/// Connection to the system clipboard, opened on first use. It stays open because on some
+/// platforms (like X11) copied data is only available as long as the connection is.
+#[derive(Default)]
+pub struct Clipboard {
+    connection: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    fn connection(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        if self.connection.is_none() {
+            self.connection = Some(arboard::Clipboard::new()?);
+        }
+        Ok(self.connection.as_mut().unwrap())
+    }
+
+    /// Read the text on the clipboard and decode it
+    pub fn read(&mut self) -> Result<Vec<u8>, String> {
+        let text = self
+            .connection()
+            .and_then(|connection| connection.get_text())
+            .map_err(|e| format!("Could not read the clipboard: {}", e))?;
+        decode(&text)
+    }
+
+    /// Put an RGBA image on the clipboard
+    pub fn copy_image(&mut self, width: usize, height: usize, pixels: &[u8]) -> Result<(), String> {
+        let image = arboard::ImageData {
+            width,
+            height,
+            bytes: Cow::Borrowed(pixels),
+        };
+        self.connection()
+            .and_then(|connection| connection.set_image(image))
+            .map_err(|e| format!("Could not copy the image to the clipboard: {}", e))
+    }
+}
+
+#[test]
+fn decode_hex_and_raw_bytes() {
+    assert_eq!(decode("de ad\nBE EF"), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+    assert_eq!(decode("0x0a, 0x0b,0X0c"), Ok(vec![0x0a, 0x0b, 0x0c]));
+    assert_eq!(decode("a\u{1}b"), Ok(vec![b'a', 1, b'b']));
+    assert!(decode("abc").is_err());
+    assert!(decode("zz").is_err());
+    assert!(decode("ä0").is_err());
+    assert!(decode(" \n").is_err());
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::options::CliOptions;
+use crate::settings::{PixelStyle, Settings, StyleSettings};
+
+/// Settings which are persisted across sessions
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub pixel_style: PixelStyle,
+    pub style_settings: StyleSettings,
+    pub max_render_size: u32,
+    pub gui_scale: f32,
+    pub panel_width: f32,
+    pub editor_command: String,
+    /// Defaults for files with a given extension (lower case, without the dot), like
+    /// '{"tex": {"pixel_style": "Rgb"}}'. They are only edited in the file.
+    pub extensions: HashMap<String, ExtensionDefaults>,
+    /// Geometry of the window when the last session ended
+    pub window: Option<WindowGeometry>,
+    /// Initial size of the window in logical pixels, instead of the one of the last session, see
+    /// '--width-px' and '--height-px'. They are only edited in the file.
+    pub window_width: Option<u32>,
+    pub window_height: Option<u32>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::from_settings(&Settings::default())
+    }
+}
+
+fn config_path(filename: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("binocle").join(filename))
+}
+
+/// Deserialize a file in the configuration directory, falling back to the defaults if it does
+/// not exist or can not be read.
+fn load_or_default<T: Default + for<'de> Deserialize<'de>>(path: Option<PathBuf>) -> T {
+    let path = match path {
+        Some(path) if path.exists() => path,
+        _ => return T::default(),
+    };
+
+    fs::read_to_string(&path)
+        .context("Could not read the configuration file")
+        .and_then(|contents| {
+            serde_json::from_str(&contents).context("Could not parse the configuration file")
+        })
+        .unwrap_or_else(|e| {
+            warn!("{:#} '{}'", e, path.display());
+            T::default()
+        })
+}
+
+fn save<T: Serialize>(path: Option<PathBuf>, value: &T) -> Result<()> {
+    let path = path.context("Could not determine the configuration directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("Could not write '{}'", path.display()))
+}
+
+impl Config {
+    fn path() -> Option<PathBuf> {
+        config_path("config.json")
+    }
+
+    /// Load the configuration file, falling back to the defaults if it does not exist or can
+    /// not be read.
+    pub fn load() -> Self {
+        load_or_default(Self::path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        save(Self::path(), self)
+    }
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            pixel_style: settings.pixel_style,
+            style_settings: settings.style_settings.clone(),
+            max_render_size: settings.max_render_size,
+            gui_scale: settings.gui_scale,
+            panel_width: settings.panel_width,
+            editor_command: settings.editor_command.clone(),
+            extensions: HashMap::new(),
+            window: None,
+            window_width: None,
+            window_height: None,
+        }
+    }
+
+    /// The defaults for the extension of the file, if any
+    pub fn extension_defaults<P: AsRef<Path>>(&self, filename: P) -> Option<&ExtensionDefaults> {
+        extension_defaults(&self.extensions, filename)
+    }
+
+    pub fn apply_to(self, settings: &mut Settings) {
+        settings.pixel_style = self.pixel_style;
+        settings.style_settings = self.style_settings;
+        settings.max_render_size = self.max_render_size;
+        settings.gui_scale = self.gui_scale;
+        settings.panel_width = self.panel_width;
+        settings.editor_command = self.editor_command;
+    }
+}
+
+/// The defaults for the extension of the file among those of the configuration, if any
+pub fn extension_defaults<P: AsRef<Path>>(
+    extensions: &HashMap<String, ExtensionDefaults>,
+    filename: P,
+) -> Option<&ExtensionDefaults> {
+    let extension = filename.as_ref().extension()?.to_string_lossy();
+    extensions.get(&extension.to_lowercase())
+}
+
+/// Initial settings for all files with a certain extension
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtensionDefaults {
+    pub pixel_style: Option<PixelStyle>,
+    pub width: Option<isize>,
+}
+
+impl ExtensionDefaults {
+    /// Use the defaults for all options which are not given otherwise
+    pub fn fill_options(&self, options: &mut CliOptions) {
+        options.style = options.style.or(self.pixel_style);
+        options.width = options.width.or(self.width);
+    }
+
+    /// Show a file which is opened while binocle runs with the defaults
+    pub fn apply_to(&self, settings: &mut Settings) {
+        if let Some(style) = self.pixel_style {
+            settings.pixel_style = style;
+        }
+        if let Some(width) = self.width {
+            settings.width = width;
+            settings.clamp();
+        }
+    }
+}
+
+/// Position and inner size of the window, or of a monitor, in physical pixels
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WindowGeometry {
+    /// The geometry shrunk and moved to lie within the monitor which contains its center, or
+    /// `None` if there is no such monitor (e.g. because it was disconnected)
+    pub fn fit_to(&self, monitors: &[WindowGeometry]) -> Option<WindowGeometry> {
+        let center = (
+            self.x as i64 + self.width as i64 / 2,
+            self.y as i64 + self.height as i64 / 2,
+        );
+        let monitor = monitors.iter().find(|monitor| {
+            (monitor.x as i64..monitor.x as i64 + monitor.width as i64).contains(&center.0)
+                && (monitor.y as i64..monitor.y as i64 + monitor.height as i64).contains(&center.1)
+        })?;
+
+        let width = self.width.min(monitor.width);
+        let height = self.height.min(monitor.height);
+        Some(WindowGeometry {
+            x: self
+                .x
+                .clamp(monitor.x, monitor.x + (monitor.width - width) as i32),
+            y: self
+                .y
+                .clamp(monitor.y, monitor.y + (monitor.height - height) as i32),
+            width,
+            height,
+        })
+    }
+}
+
+/// Layout of the view of a single file, restored when the file is opened again
+#[derive(Serialize, Deserialize)]
+pub struct ViewState {
+    /// Canonical path of the file, used to prune the states of deleted files
+    pub path: PathBuf,
+    pub offset: i64,
+    pub width: isize,
+    pub stride: isize,
+    pub zoom: isize,
+    #[serde(default)]
+    pub base_address: Option<i64>,
+}
+
+impl ViewState {
+    pub fn from_settings(path: PathBuf, settings: &Settings) -> Self {
+        Self {
+            path,
+            offset: settings.view_start(),
+            width: settings.width,
+            stride: settings.stride,
+            zoom: settings.zoom,
+            base_address: (settings.address_base != 0).then_some(settings.address_base),
+        }
+    }
+
+    /// Show the stored layout, e.g. for a file which is opened while binocle runs
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.offset = self.offset;
+        settings.offset_fine = 0;
+        settings.width = self.width;
+        settings.stride = self.stride;
+        settings.zoom = self.zoom;
+        settings.address_base = self.base_address.unwrap_or(0);
+        settings.show_addresses = self.base_address.is_some();
+        settings.clamp();
+    }
+
+    /// Use the stored layout for all options which are not given on the command line
+    pub fn fill_options(&self, options: &mut CliOptions) {
+        options.offset = options.offset.or(Some(self.offset));
+        options.width = options.width.or(Some(self.width));
+        options.stride = options.stride.or(Some(self.stride));
+        options.zoom = options.zoom.or(Some(self.zoom));
+        options.base_address = options.base_address.or(self.base_address);
+    }
+}
+
+/// View states of all previously opened files, keyed by a hash of their canonical path
+#[derive(Default, Serialize, Deserialize)]
+pub struct ViewStates(HashMap<String, ViewState>);
+
+impl ViewStates {
+    fn path() -> Option<PathBuf> {
+        config_path("views.json")
+    }
+
+    /// Canonical path of the file and the key of its view state
+    pub fn key<P: AsRef<Path>>(filename: P) -> Option<(PathBuf, String)> {
+        let path = fs::canonicalize(filename).ok()?;
+        let hash = blake3::hash(path.to_string_lossy().as_bytes());
+        Some((path, hash.to_hex().to_string()))
+    }
+
+    pub fn load() -> Self {
+        load_or_default(Self::path())
+    }
+
+    pub fn get<P: AsRef<Path>>(&self, filename: P) -> Option<&ViewState> {
+        Self::key(filename).and_then(|(_, key)| self.0.get(&key))
+    }
+
+    pub fn insert<P: AsRef<Path>>(&mut self, filename: P, settings: &Settings) {
+        if let Some((path, key)) = Self::key(filename) {
+            self.0.insert(key, ViewState::from_settings(path, settings));
+        }
+    }
+
+    /// Remove the states of files which no longer exist, then write the file
+    pub fn save(&mut self) -> Result<()> {
+        self.0.retain(|_, state| state.path.exists());
+        save(Self::path(), self)
+    }
+}
+
+#[test]
+fn extension_defaults_ignore_case() {
+    let mut config = Config::default();
+    config.extensions.insert(
+        "tex".into(),
+        ExtensionDefaults {
+            pixel_style: Some(PixelStyle::Rgb),
+            width: None,
+        },
+    );
+    let style = |filename| {
+        config
+            .extension_defaults(filename)
+            .and_then(|defaults| defaults.pixel_style)
+    };
+    assert_eq!(style("textures/foo.TEX"), Some(PixelStyle::Rgb));
+    assert_eq!(style("foo.bin"), None);
+    assert_eq!(style("tex"), None);
+}
+
+#[test]
+fn window_geometry_fits_to_monitors() {
+    let monitors = [
+        WindowGeometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        },
+        WindowGeometry {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 1024,
+        },
+    ];
+    let window = |x, y, width, height| WindowGeometry {
+        x,
+        y,
+        width,
+        height,
+    };
+    assert_eq!(
+        window(100, 50, 800, 600).fit_to(&monitors),
+        Some(window(100, 50, 800, 600))
+    );
+    assert_eq!(
+        window(2000, -20, 1400, 1100).fit_to(&monitors),
+        Some(window(1920, 0, 1280, 1024))
+    );
+    // The monitor on the right was disconnected
+    assert_eq!(window(2000, 0, 800, 600).fit_to(&monitors[..1]), None);
+}
@@ -0,0 +1,35 @@
+use std::panic;
+use std::process;
+use std::sync::Mutex;
+
+use crate::settings::Settings;
+
+/// Description of the current view, reported if binocle panics
+static CONTEXT: Mutex<String> = Mutex::new(String::new());
+
+/// Install a panic hook that reports the file and the current view settings before exiting,
+/// such that bug reports contain everything needed to reproduce the problem.
+pub fn install_panic_hook(filename: &str) {
+    let filename = filename.to_owned();
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        eprintln!();
+        eprintln!("binocle crashed while viewing '{}'", filename);
+        // Do not block if the panic happened while the context was being updated
+        if let Ok(context) = CONTEXT.try_lock() {
+            eprintln!("{}", context);
+        }
+        eprintln!("Please report this at https://github.com/sharkdp/binocle/issues");
+
+        process::exit(101);
+    }));
+}
+
+pub fn update_context(settings: &Settings) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        *context = settings.summary();
+    }
+}
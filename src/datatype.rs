@@ -1,12 +1,14 @@
 use std::convert::TryInto;
 
-#[derive(Clone, Copy, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Endianness {
     Big,
     Little,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Signedness {
     Unsigned,
     Signed,
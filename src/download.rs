@@ -0,0 +1,48 @@
+//! Opening files at HTTP(S) URLs, which needs the optional 'url' feature
+
+use std::io::Read;
+
+use anyhow::Result;
+
+/// Whether the file name given on the command line is a URL instead of a path
+pub fn is_url(filename: &str) -> bool {
+    filename.starts_with("http://") || filename.starts_with("https://")
+}
+
+/// A download of which only the headers are received yet
+pub struct Download {
+    /// Size announced by the server, if any
+    pub size: Option<u64>,
+    pub reader: Box<dyn Read + Send>,
+}
+
+/// Request the file at the URL. HTTP errors like '404 Not Found' are returned as errors.
+#[cfg(feature = "url")]
+pub fn start(url: &str) -> Result<Download> {
+    let response = ureq::get(url).call().map_err(|e| match e {
+        ureq::Error::Status(code, response) => {
+            anyhow::anyhow!("HTTP error {} {}", code, response.status_text())
+        }
+        e => e.into(),
+    })?;
+    let size = response
+        .header("Content-Length")
+        .and_then(|length| length.parse().ok());
+    Ok(Download {
+        size,
+        reader: Box::new(response.into_reader()),
+    })
+}
+
+#[cfg(not(feature = "url"))]
+pub fn start(_url: &str) -> Result<Download> {
+    anyhow::bail!("This build of binocle can not open URLs, it needs the 'url' feature")
+}
+
+#[test]
+fn urls_are_told_from_paths() {
+    assert!(is_url("https://example.com/sample.bin"));
+    assert!(is_url("http://localhost:8000/a"));
+    assert!(!is_url("sample.bin"));
+    assert!(!is_url("/home/user/https://"));
+}
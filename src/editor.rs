@@ -0,0 +1,38 @@
+use std::io;
+use std::process::Command;
+
+/// Default command to open the file in an external hex editor
+pub const DEFAULT_EDITOR_COMMAND: &str = "ghex {file}";
+
+/// Split the command template at whitespace and fill in the '{file}' and '{offset}' placeholders
+fn command_line(template: &str, file: &str, offset: i64) -> Vec<String> {
+    template
+        .split_whitespace()
+        .map(|arg| {
+            arg.replace("{file}", file)
+                .replace("{offset}", &offset.to_string())
+        })
+        .collect()
+}
+
+/// Start the editor without waiting for it to be closed
+pub fn spawn(template: &str, file: &str, offset: i64) -> io::Result<()> {
+    let args = command_line(template, file, offset);
+    let (program, args) = args.split_first().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "The editor command is empty")
+    })?;
+    let mut child = Command::new(program).args(args).spawn()?;
+
+    // Reap the process once it exits
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[test]
+fn command_line_placeholders() {
+    assert_eq!(
+        command_line("hexedit  --seek {offset} {file}", "/tmp/a b", 42),
+        ["hexedit", "--seek", "42", "/tmp/a b"]
+    );
+    assert_eq!(command_line("  ", "f", 0), Vec::<String>::new());
+}
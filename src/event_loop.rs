@@ -1,89 +1,296 @@
-use std::path::Path;
-
 use anyhow::Result;
-use log::error;
-use pixels::{Pixels, SurfaceTexture};
-use winit::dpi::LogicalSize;
-use winit::event::{Event, VirtualKeyCode};
+use log::{error, warn};
+use pixels::{wgpu, Pixels, PixelsBuilder, SurfaceTexture};
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::{Event, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::{Fullscreen, WindowBuilder};
-use winit_input_helper::WinitInputHelper;
+use winit::window::{Fullscreen, Window, WindowBuilder};
+use winit_input_helper::{TextChar, WinitInputHelper};
 
+use crate::action_log::{ActionRecorder, ActionReplay};
 use crate::binocle::Binocle;
+use crate::config::{self, Config, ViewStates, WindowGeometry};
+use crate::crash;
 use crate::gui::Gui;
 use crate::options::CliOptions;
-use crate::settings::{HEIGHT, WIDTH};
+use crate::scaling::ScalingRenderer;
+use crate::settings::{EntropyJump, Selection, HEIGHT, MIN_WINDOW_HEIGHT, MIN_WINDOW_WIDTH, WIDTH};
+
+/// Keys and mouse actions with what they do, as listed in the help window. This has to be kept in
+/// sync with the handling of the input below.
+pub const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("F1, ?", "Show or hide this help"),
+    ("Escape, Q", "Quit"),
+    ("Tab", "Show or hide the GUI"),
+    ("F", "Toggle fullscreen"),
+    ("A", "Fit the whole file on the canvas, or go back"),
+    ("R", "Reset the view"),
+    ("E / Shift+E", "Jump to the highest / lowest entropy"),
+    ("O", "Open the file in the external editor"),
+    ("F5", "Reload the file"),
+    ("H", "Show or hide the hex view"),
+    ("S", "Switch to the previously used style"),
+    (
+        "P / Shift+P",
+        "Pin the row under the cursor at the top / unpin it",
+    ),
+    (
+        "V / Shift+V",
+        "Show the next / previous version of the file",
+    ),
+    ("Ctrl+V", "Load bytes from the clipboard"),
+    ("Ctrl+C", "Copy the view to the clipboard as an image"),
+    ("+ / -", "Zoom in / out"),
+    ("Left / Right", "Decrease / increase the width"),
+    (
+        "Up / Down",
+        "Scroll by one row (with Shift: by one byte, with Ctrl: by one screen)",
+    ),
+    ("PageUp / PageDown", "Scroll by one screen"),
+    ("Home / End", "Go to the start / end of the file"),
+    ("N / M", "Move the offset by one byte"),
+    (", / .", "Decrease / increase the stride"),
+    ("1 … 9", "Set the stride"),
+    ("Mouse wheel", "Scroll (with Ctrl: zoom, with Alt: width)"),
+    ("Left drag", "Scroll (with Shift: fine offset)"),
+    ("Right drag", "Change the width"),
+    ("Ctrl+left drag", "Select a range of bytes"),
+];
 
 enum MouseDragAction {
     Nothing,
     ControlOffset {
         start_y: f32,
-        start_offset: isize,
+        start_offset: i64,
     },
     ControlOffsetFine {
         start_x: f32,
-        start_offset_fine: isize,
+        start_offset: i64,
+        start_offset_fine: i64,
     },
     ControlWidth {
         start_x: f32,
         start_width: isize,
     },
+    Select,
 }
 
-pub fn run(options: CliOptions) -> Result<()> {
-    env_logger::init();
+/// Create the pixel buffer with the default GPU backends. If no adapter is found (like in VMs or
+/// containers without GPU access), fall back to the OpenGL backend, which drivers like Mesa can
+/// render in software. With 'software', this software rendering is forced.
+fn create_pixels(window: &Window, size: PhysicalSize<u32>, software: bool) -> Result<Pixels> {
+    let builder = || {
+        PixelsBuilder::new(
+            WIDTH,
+            HEIGHT,
+            SurfaceTexture::new(size.width, size.height, window),
+        )
+    };
+    let opengl = || builder().wgpu_backend(wgpu::Backends::GL).build();
+
+    if software {
+        warn!("Using software rendering");
+        std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+        return Ok(opengl()?);
+    }
+    builder().build().or_else(|e| {
+        warn!(
+            "Could not initialize the GPU ({}), falling back to OpenGL with possibly software \
+             rendering",
+            e
+        );
+        Ok(opengl()?)
+    })
+}
+
+/// Position and size of the window to restore in the next session, unless it is in fullscreen or
+/// minimized
+fn window_geometry(window: &Window) -> Option<WindowGeometry> {
+    let size = window.inner_size();
+    if window.fullscreen().is_some() || size.width == 0 || size.height == 0 {
+        return None;
+    }
+    let position = window.outer_position().ok()?;
+    Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+pub fn run(mut options: CliOptions) -> Result<()> {
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
+    let config = Config::load();
     let window = {
         let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
-        WindowBuilder::new()
-            .with_title(format!(
-                "binocle - {}",
-                Path::new(&options.filename)
-                    .file_name()
-                    .map(|f| f.to_string_lossy())
-                    .as_deref()
-                    .unwrap_or("<unknown>")
-            ))
+        let min_size = LogicalSize::new(MIN_WINDOW_WIDTH as f64, MIN_WINDOW_HEIGHT as f64);
+        let mut builder = WindowBuilder::new()
+            .with_title(format!("binocle - {}", options.filename))
             .with_inner_size(size)
-            .with_min_inner_size(size)
-            .build(&event_loop)
-            .unwrap()
+            .with_min_inner_size(min_size);
+
+        // Restore the geometry of the last session, if it is still on one of the monitors
+        let monitors: Vec<_> = event_loop
+            .available_monitors()
+            .map(|monitor| WindowGeometry {
+                x: monitor.position().x,
+                y: monitor.position().y,
+                width: monitor.size().width,
+                height: monitor.size().height,
+            })
+            .collect();
+        if let Some(geometry) = config.window.and_then(|window| window.fit_to(&monitors)) {
+            builder = builder
+                .with_inner_size(PhysicalSize::new(geometry.width, geometry.height))
+                .with_position(PhysicalPosition::new(geometry.x, geometry.y));
+        }
+
+        // A size given on the command line or in the configuration replaces the one of the last
+        // session, a dimension which is not given is the default one
+        let width = options.width_px.or(config.window_width);
+        let height = options.height_px.or(config.window_height);
+        if width.is_some() || height.is_some() {
+            builder = builder.with_inner_size(LogicalSize::new(
+                width.unwrap_or(WIDTH).max(MIN_WINDOW_WIDTH) as f64,
+                height.unwrap_or(HEIGHT).max(MIN_WINDOW_HEIGHT) as f64,
+            ));
+        }
+        builder.build(&event_loop).unwrap()
     };
 
-    let (mut pixels, mut gui) = {
+    let (mut pixels, mut scaling_renderer, mut gui) = {
         let window_size = window.inner_size();
         let scale_factor = window.scale_factor();
-        let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        let pixels = Pixels::new(WIDTH, HEIGHT, surface_texture)?;
+        let pixels = create_pixels(&window, window_size, options.software)?;
+        let scaling_renderer = ScalingRenderer::new(
+            &pixels,
+            (WIDTH, HEIGHT),
+            (window_size.width, window_size.height),
+        );
         let gui = Gui::new(window_size.width, window_size.height, scale_factor, &pixels);
 
-        (pixels, gui)
+        (pixels, scaling_renderer, gui)
     };
 
-    let mut binocle = Binocle::new(options)?;
+    // Restore the layout of the last session with this file, unless overridden on the command line,
+    // and fall back to the defaults for the extension of the file
+    let mut view_states = ViewStates::load();
+    if let Some(view_state) = view_states.get(&options.filename) {
+        view_state.fill_options(&mut options);
+    }
+    if let Some(defaults) = config.extension_defaults(&options.filename) {
+        defaults.fill_options(&mut options);
+    }
+    let record_path = options.record.clone();
+    let replay_path = options.replay.clone();
+
+    let style = options.style;
+    let extensions = config.extensions.clone();
+    let last_window = config.window;
+    let window_size = (config.window_width, config.window_height);
+    let mut binocle = Binocle::new(options, true)?;
+    config.apply_to(&mut binocle.settings);
+    if let Some(style) = style {
+        binocle.settings.pixel_style = style;
+    }
+    binocle.settings.keep_as_startup();
+
+    let mut recorder = record_path.map(ActionRecorder::create).transpose()?;
+    let mut replay = replay_path.map(ActionReplay::load).transpose()?;
 
     let mut mouse_drag_action = MouseDragAction::Nothing;
+    let mut shown_title = String::new();
 
     event_loop.run(move |event, _, control_flow| {
         // Update egui inputs
         gui.handle_event(&event);
 
+        // Persist the configuration when the application exits
+        if let Event::LoopDestroyed = event {
+            let config = Config {
+                extensions: extensions.clone(),
+                window: window_geometry(&window).or(last_window),
+                window_width: window_size.0,
+                window_height: window_size.1,
+                ..Config::from_settings(&binocle.settings)
+            };
+            if let Err(e) = config.save() {
+                error!("{:#}", e);
+            }
+            // The layout of a file which was not opened yet is not meaningful
+            if let (None, Some(path)) = (&binocle.settings.loading, binocle.path()) {
+                view_states.insert(path, &binocle.settings);
+                if let Err(e) = view_states.save() {
+                    error!("{:#}", e);
+                }
+            }
+            return;
+        }
+
+        // The close button of the window quits in any case, even while the GUI has the keyboard
+        // focus (the key bindings below are skipped then, including 'input.quit()')
+        if let Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } = event
+        {
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+
+        // Open a file dropped on the window, after keeping the layout of the current one
+        if let Event::WindowEvent {
+            event: WindowEvent::DroppedFile(path),
+            ..
+        } = &event
+        {
+            let path = path.to_string_lossy().into_owned();
+            // The layout of a file which is still loaded is not the one it was shown with
+            if let (None, Some(path)) = (&binocle.settings.loading, binocle.path()) {
+                view_states.insert(path, &binocle.settings);
+            }
+            match binocle.open(&path) {
+                Ok(()) => {
+                    if !binocle.settings.keep_layout {
+                        if let Some(defaults) = config::extension_defaults(&extensions, &path) {
+                            defaults.apply_to(&mut binocle.settings);
+                        }
+                        if let Some(view_state) = view_states.get(&path) {
+                            view_state.apply_to(&mut binocle.settings);
+                        }
+                        binocle.settings.keep_as_startup();
+                    }
+                }
+                Err(e) => binocle.settings.status = format!("{:#}", e),
+            }
+        }
+
         // Draw the current frame
         if let Event::RedrawRequested(_) = event {
             // Draw the binocle
             binocle.draw(pixels.get_frame());
 
             // Prepare egui
-            gui.prepare(&window, &mut binocle.settings);
+            let gui_visible = binocle.settings.gui_visible;
+            if gui_visible {
+                gui.prepare(&window, &mut binocle.settings);
+            } else {
+                binocle.settings.gui_wants_keyboard = false;
+                binocle.settings.gui_wants_mouse = false;
+            }
 
             // Render everything together
+            let scaling_filter = binocle.settings.scaling_filter;
             let render_result = pixels.render_with(|encoder, render_target, context| {
                 // Render the binocle texture
-                context.scaling_renderer.render(encoder, render_target);
+                scaling_renderer.render(encoder, render_target, scaling_filter);
 
                 // Render egui
-                gui.render(encoder, render_target, context)?;
+                if gui_visible {
+                    gui.render(encoder, render_target, context)?;
+                }
 
                 Ok(())
             });
@@ -101,13 +308,28 @@ pub fn run(options: CliOptions) -> Result<()> {
         // Handle input events
         if input.update(&event) {
             {
-                let mut settings = &mut binocle.settings;
+                let settings = &mut binocle.settings;
 
+                // The mouse wheel scrolls by many rows, or with shift by one row
                 let offset_factor = if input.held_shift() { 1 } else { 160 };
 
                 if !settings.gui_wants_keyboard {
-                    // Close events
-                    if input.key_pressed(VirtualKeyCode::Escape)
+                    // Show or hide the help, which also needs the GUI
+                    if input.key_pressed(VirtualKeyCode::F1)
+                        || input
+                            .text()
+                            .iter()
+                            .any(|c| matches!(c, TextChar::Char('?')))
+                    {
+                        settings.help_visible = !settings.help_visible;
+                        settings.gui_visible |= settings.help_visible;
+                    }
+
+                    // Quit with Escape or Q, or when the window was destroyed. Escape closes the
+                    // help first.
+                    if settings.help_visible && input.key_pressed(VirtualKeyCode::Escape) {
+                        settings.help_visible = false;
+                    } else if input.key_pressed(VirtualKeyCode::Escape)
                         || input.key_pressed(VirtualKeyCode::Q)
                         || input.quit()
                     {
@@ -124,6 +346,71 @@ pub fn run(options: CliOptions) -> Result<()> {
                         }
                     }
 
+                    // Fit the whole file on the canvas, or go back to the previous layout
+                    if input.key_pressed(VirtualKeyCode::A) {
+                        settings.toggle_fit_to_file();
+                    }
+
+                    // Reset the settings to the ones the file was opened with
+                    if input.key_pressed(VirtualKeyCode::R) {
+                        settings.reset();
+                    }
+
+                    // Switch back and forth between the last two styles
+                    if input.key_pressed(VirtualKeyCode::S) {
+                        settings.toggle_pixel_style();
+                    }
+
+                    // Show or hide the whole GUI
+                    if input.key_pressed(VirtualKeyCode::Tab) {
+                        settings.gui_visible = !settings.gui_visible;
+                    }
+
+                    // Jump to the highest (or with shift the lowest) entropy
+                    if input.key_pressed(VirtualKeyCode::E) {
+                        settings.entropy_jump_requested = Some(if input.held_shift() {
+                            EntropyJump::Lowest
+                        } else {
+                            EntropyJump::Highest
+                        });
+                    }
+
+                    // Open the file in the external hex editor
+                    if input.key_pressed(VirtualKeyCode::O) {
+                        settings.editor_requested = true;
+                    }
+
+                    // Read the file again
+                    if input.key_pressed(VirtualKeyCode::F5) {
+                        settings.reload_requested = true;
+                    }
+
+                    // Replace the data with the bytes from the clipboard, or show the next (with
+                    // shift the previous) version of the file
+                    if input.key_pressed(VirtualKeyCode::V) {
+                        if input.held_control() {
+                            settings.clipboard_requested = true;
+                        } else {
+                            settings.versions.step_requested =
+                                Some(if input.held_shift() { -1 } else { 1 });
+                        }
+                    }
+
+                    // Copy the view to the clipboard as an image
+                    if input.held_control() && input.key_pressed(VirtualKeyCode::C) {
+                        settings.copy_image_requested = true;
+                    }
+
+                    // Pin the row under the cursor as a reference at the top, or with shift
+                    // remove it again
+                    if input.key_pressed(VirtualKeyCode::P) {
+                        if input.held_shift() {
+                            settings.pinned_row = None;
+                        } else {
+                            settings.pin_row_requested = true;
+                        }
+                    }
+
                     // Hex view
                     if input.key_pressed(VirtualKeyCode::H) {
                         settings.hex_view_visible = !settings.hex_view_visible;
@@ -140,15 +427,26 @@ pub fn run(options: CliOptions) -> Result<()> {
                     }
 
                     if input.key_pressed(VirtualKeyCode::Left) {
-                        settings.width -= 1;
+                        settings.width -= settings.pixels_per_byte();
                     } else if input.key_pressed(VirtualKeyCode::Right) {
-                        settings.width += 1;
+                        settings.width += settings.pixels_per_byte();
                     }
 
-                    if input.key_pressed(VirtualKeyCode::Up) {
-                        settings.offset -= offset_factor * settings.width * settings.stride;
+                    // Move by a row, with Shift by a single byte of the fine offset (which
+                    // carries over into the offset) and with Ctrl by a screen
+                    let direction = if input.key_pressed(VirtualKeyCode::Up) {
+                        -1
                     } else if input.key_pressed(VirtualKeyCode::Down) {
-                        settings.offset += offset_factor * settings.width * settings.stride;
+                        1
+                    } else {
+                        0
+                    };
+                    if input.held_shift() {
+                        settings.offset_fine += direction;
+                    } else if input.held_control() {
+                        settings.offset += direction * settings.page_length();
+                    } else {
+                        settings.offset += direction * settings.row_length();
                     }
 
                     if input.key_pressed(VirtualKeyCode::N) {
@@ -164,9 +462,9 @@ pub fn run(options: CliOptions) -> Result<()> {
                     }
 
                     if input.key_pressed(VirtualKeyCode::PageUp) {
-                        settings.offset -= settings.width * settings.stride * (HEIGHT as isize);
+                        settings.offset -= settings.page_length();
                     } else if input.key_pressed(VirtualKeyCode::PageDown) {
-                        settings.offset += settings.width * settings.stride * (HEIGHT as isize);
+                        settings.offset += settings.page_length();
                     }
 
                     if input.key_pressed(VirtualKeyCode::Key1)
@@ -211,30 +509,42 @@ pub fn run(options: CliOptions) -> Result<()> {
                         settings.offset = 0;
                         settings.offset_fine = 0;
                     } else if input.key_pressed(VirtualKeyCode::End) {
-                        settings.offset = settings.buffer_length
-                            - settings.width * (HEIGHT as isize) * settings.stride;
+                        settings.offset = settings.buffer_length - settings.page_length();
                         settings.offset_fine = 0;
                     }
                 }
 
+                settings.cursor = input
+                    .mouse()
+                    .filter(|_| !settings.gui_wants_mouse)
+                    .and_then(|pos| scaling_renderer.window_pos_to_pixel(pos));
+
                 if !settings.gui_wants_mouse {
                     if input.scroll_diff().abs() > 0.5 {
                         let scroll = input.scroll_diff() as isize;
                         if input.held_control() {
                             settings.zoom += scroll;
                         } else if input.held_alt() {
-                            settings.width += scroll;
+                            settings.width += scroll * settings.pixels_per_byte();
                         } else {
                             settings.offset -=
-                                offset_factor * scroll * settings.width * settings.stride;
+                                offset_factor * (scroll as i64) * settings.row_length();
                         }
                     }
 
                     if let Some((x, y)) = input.mouse() {
                         if input.mouse_pressed(0) {
-                            if input.held_shift() {
+                            if input.held_control() {
+                                mouse_drag_action = MouseDragAction::Select;
+                                settings.selection =
+                                    settings.cursor_offset().map(|offset| Selection {
+                                        anchor: offset,
+                                        end: offset,
+                                    });
+                            } else if input.held_shift() {
                                 mouse_drag_action = MouseDragAction::ControlOffsetFine {
                                     start_x: x,
+                                    start_offset: settings.offset,
                                     start_offset_fine: settings.offset_fine,
                                 };
                             } else {
@@ -264,20 +574,22 @@ pub fn run(options: CliOptions) -> Result<()> {
                                     start_offset,
                                 } => {
                                     let delta_y = (y - start_y) / zoom_factor;
-                                    let min_offset =
-                                        start_offset % (settings.width * settings.stride);
+                                    let min_offset = start_offset % settings.row_length();
                                     settings.offset = min_offset.max(
-                                        start_offset
-                                            - (delta_y as isize) * settings.width * settings.stride,
+                                        start_offset - (delta_y as i64) * settings.row_length(),
                                     );
                                 }
                                 MouseDragAction::ControlOffsetFine {
                                     start_x,
+                                    start_offset,
                                     start_offset_fine,
                                 } => {
+                                    // Start from the initial state in every step, such that
+                                    // carries into the coarse offset do not accumulate
                                     let delta_x = (x - start_x) / zoom_factor;
-                                    settings.offset_fine =
-                                        start_offset_fine - (delta_x as isize) * settings.stride;
+                                    settings.offset = start_offset;
+                                    settings.offset_fine = start_offset_fine
+                                        - (delta_x as i64) * (settings.stride as i64);
                                 }
                                 MouseDragAction::ControlWidth {
                                     start_x,
@@ -286,30 +598,30 @@ pub fn run(options: CliOptions) -> Result<()> {
                                     let delta_x = (x - start_x) / zoom_factor;
                                     settings.width = start_width + (delta_x as isize);
                                 }
+                                MouseDragAction::Select => {
+                                    let offset = settings.cursor_offset();
+                                    if let (Some(selection), Some(offset)) =
+                                        (&mut settings.selection, offset)
+                                    {
+                                        selection.end = offset;
+                                    }
+                                }
                                 MouseDragAction::Nothing => {}
                             }
                         }
                     }
                 }
 
-                settings.zoom = settings.zoom.max(settings.zoom_range.0);
-                settings.zoom = settings.zoom.min(settings.zoom_range.1);
-
-                settings.width = settings.width.max(1);
-                settings.width = settings.width.min(settings.max_width());
-
-                settings.offset = settings.offset.max(0);
-                settings.offset = settings.offset.min(settings.buffer_length);
-
-                settings.offset_fine = settings.offset_fine.max(0);
-                settings.offset_fine = settings.offset_fine.min(settings.max_offset_fine());
+                if let Some(action_replay) = &mut replay {
+                    if !action_replay.apply(settings) {
+                        replay = None;
+                    }
+                }
 
-                settings.stride = settings.stride.max(1);
-                settings.stride = settings.stride.min(settings.max_stride);
+                settings.clamp();
 
-                // Update the scale factor
-                if let Some(scale_factor) = input.scale_factor() {
-                    gui.scale_factor(scale_factor);
+                if let Some(recorder) = &mut recorder {
+                    recorder.record(settings);
                 }
 
                 // Resize the window
@@ -317,9 +629,63 @@ pub fn run(options: CliOptions) -> Result<()> {
                     pixels.resize_surface(size.width, size.height);
                     gui.resize(size.width, size.height);
                 }
+
+                // The pixel buffer follows the size of the area left of the side panel, but is
+                // kept below the maximum render size and scaled up instead
+                let window_size = window.inner_size();
+                let area = settings.canvas_area(
+                    window_size.width,
+                    window_size.height,
+                    gui.pixels_per_point(),
+                );
+                let (width, height) = settings.render_size(area.0, area.1);
+                if (width as isize, height as isize)
+                    != (settings.canvas_width, settings.canvas_height)
+                {
+                    pixels.resize_buffer(width, height);
+                    scaling_renderer = ScalingRenderer::new(&pixels, (width, height), area);
+                    settings.canvas_width = width as isize;
+                    settings.canvas_height = height as isize;
+                } else if area != scaling_renderer.area_size() {
+                    scaling_renderer.resize(pixels.queue(), area.0, area.1);
+                }
             }
 
+            crash::update_context(&binocle.settings);
+            binocle.settings.track_pixel_style();
+            binocle.update_loading();
+            binocle.update_versions();
+            binocle.update_detach();
+            binocle.update_reload();
+            binocle.update_padding();
+            binocle.update_clipboard();
+            binocle.update_run_length_levels();
+            binocle.update_search();
+            binocle.update_entropy_jump();
+            binocle.update_entropy_timeline();
+            binocle.update_editor();
             binocle.update_hex_view();
+            binocle.update_inspector();
+            binocle.update_regions();
+            binocle.update_column_profile();
+            binocle.update_sparklines();
+            binocle.update_row_graph();
+            binocle.update_contrast();
+            binocle.update_spectrum();
+            binocle.update_width_scores();
+            binocle.update_row_correlation();
+            binocle.update_annotations();
+            binocle.update_export();
+            binocle.update_color_scale();
+            binocle.update_pinned_row();
+
+            // The title names the shown data, which changes with the versions, dropped files and
+            // the clipboard
+            let title = format!("binocle - {}", binocle.filename());
+            if title != shown_title {
+                window.set_title(&title);
+                shown_title = title;
+            }
             window.request_redraw();
         }
     });
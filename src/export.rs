@@ -0,0 +1,150 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::font::{self, GLYPH_HEIGHT};
+use crate::style::Color;
+
+/// An RGBA image, as rendered by the headless render path
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![0; (width as usize) * (height as usize) * 4],
+        }
+    }
+
+    /// Downscale the image (nearest neighbor) such that its longer side is at most 'max_size'
+    pub fn thumbnail(&self, max_size: u32) -> Image {
+        let longer_side = self.width.max(self.height).max(1);
+        if longer_side <= max_size {
+            return Image {
+                width: self.width,
+                height: self.height,
+                pixels: self.pixels.clone(),
+            };
+        }
+
+        let width = (self.width as u64 * max_size as u64 / longer_side as u64).max(1) as u32;
+        let height = (self.height as u64 * max_size as u64 / longer_side as u64).max(1) as u32;
+
+        let mut thumbnail = Image::new(width, height);
+        for (i, pixel) in thumbnail.pixels.chunks_exact_mut(4).enumerate() {
+            let x = (i as u64 % width as u64) * self.width as u64 / width as u64;
+            let y = (i as u64 / width as u64) * self.height as u64 / height as u64;
+            let source = ((y * self.width as u64 + x) * 4) as usize;
+            pixel.copy_from_slice(&self.pixels[source..source + 4]);
+        }
+        thumbnail
+    }
+
+    /// Copy of the image with 'height' additional rows at the bottom, filled with 'color'
+    pub fn with_footer(&self, height: u32, color: Color) -> Image {
+        let mut image = Image::new(self.width, self.height + height);
+        image.pixels[..self.pixels.len()].copy_from_slice(&self.pixels);
+        for pixel in image.pixels[self.pixels.len()..].chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+        image
+    }
+
+    /// Set a single pixel, ignoring positions outside of the image
+    pub fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
+        if x < self.width && y < self.height {
+            let index = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
+            self.pixels[index..index + 4].copy_from_slice(&color);
+        }
+    }
+
+    pub fn fill_rect(&mut self, (x, y): (u32, u32), (width, height): (u32, u32), color: Color) {
+        for dy in 0..height {
+            for dx in 0..width {
+                self.put_pixel(x + dx, y + dy, color);
+            }
+        }
+    }
+
+    /// Draw a single line of text with its top left corner at (x, y), each font pixel being
+    /// 'scale' × 'scale' image pixels large. Text beyond the right border is clipped.
+    pub fn draw_text(&mut self, (x, y): (u32, u32), text: &str, scale: u32, color: Color) {
+        font::draw_text(&mut self.pixels, self.width, (x, y), text, scale, color);
+    }
+
+    /// Height of a line of text drawn with 'draw_text', including spacing
+    pub fn line_height(scale: u32) -> u32 {
+        (GLYPH_HEIGHT + 3) * scale
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("Could not create '{}'", path.display()))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+
+        Ok(())
+    }
+}
+
+/// Text of a palette file with one color per byte value: a GIMP palette if the path ends in
+/// '.gpl', otherwise CSV lines of 'byte,red,green,blue'
+pub fn palette_text(path: &str, name: &str, colors: &[Color]) -> String {
+    let gpl = path.to_ascii_lowercase().ends_with(".gpl");
+    let mut text = if gpl {
+        format!("GIMP Palette\nName: {}\nColumns: 16\n#\n", name)
+    } else {
+        "byte,red,green,blue\n".to_string()
+    };
+    for (byte, [r, g, b, _]) in colors.iter().enumerate() {
+        text += &if gpl {
+            format!("{:3} {:3} {:3}\t0x{:02x}\n", r, g, b, byte)
+        } else {
+            format!("{},{},{},{}\n", byte, r, g, b)
+        };
+    }
+    text
+}
+
+pub fn save_palette(path: &str, name: &str, colors: &[Color]) -> Result<()> {
+    std::fs::write(path, palette_text(path, name, colors))
+        .with_context(|| format!("Could not write '{}'", path))
+}
+
+#[test]
+fn palette_formats() {
+    let colors = [[0, 0, 0, 255], [255, 128, 1, 255]];
+    assert_eq!(
+        palette_text("a.gpl", "binocle", &colors),
+        "GIMP Palette\nName: binocle\nColumns: 16\n#\n  0   0   0\t0x00\n255 128   1\t0x01\n"
+    );
+    assert_eq!(
+        palette_text("a.csv", "binocle", &colors),
+        "byte,red,green,blue\n0,0,0,0\n1,255,128,1\n"
+    );
+}
+
+#[test]
+fn footer_keeps_image_content() {
+    let mut image = Image::new(2, 1);
+    image.pixels.copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let image = image.with_footer(1, [9, 9, 9, 255]);
+    assert_eq!((image.width, image.height), (2, 2));
+    assert_eq!(
+        image.pixels,
+        vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 9, 9, 255, 9, 9, 9, 255]
+    );
+}
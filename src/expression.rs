@@ -0,0 +1,116 @@
+//! A tiny evaluator for integer expressions like '16*40' or '0x1000 + 2 * (3 - 1)'
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Evaluate an expression of decimal and hexadecimal ('0x…') integers with '+', '-', '*' and
+/// parentheses, using the usual precedence rules.
+pub fn evaluate(expression: &str) -> Result<i64, String> {
+    let mut parser = Parser {
+        chars: expression.chars().peekable(),
+    };
+    let value = parser.sum()?;
+    parser.skip_whitespace();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some(c) => Err(format!("Unexpected '{}'", c)),
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn sum(&mut self) -> Result<i64, String> {
+        let mut value = self.product()?;
+        loop {
+            self.skip_whitespace();
+            let operator = match self.chars.next_if(|&c| c == '+' || c == '-') {
+                Some(operator) => operator,
+                None => return Ok(value),
+            };
+            let rhs = self.product()?;
+            value = if operator == '+' {
+                value.checked_add(rhs)
+            } else {
+                value.checked_sub(rhs)
+            }
+            .ok_or("Overflow")?;
+        }
+    }
+
+    fn product(&mut self) -> Result<i64, String> {
+        let mut value = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&'*').is_none() {
+                return Ok(value);
+            }
+            value = value.checked_mul(self.factor()?).ok_or("Overflow")?;
+        }
+    }
+
+    fn factor(&mut self) -> Result<i64, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('-') => {
+                self.chars.next();
+                self.factor()?
+                    .checked_neg()
+                    .ok_or_else(|| "Overflow".into())
+            }
+            Some('(') => {
+                self.chars.next();
+                let value = self.sum()?;
+                self.skip_whitespace();
+                match self.chars.next() {
+                    Some(')') => Ok(value),
+                    _ => Err("Missing ')'".into()),
+                }
+            }
+            Some(c) if c.is_ascii_digit() => self.number(),
+            Some(c) => Err(format!("Unexpected '{}'", c)),
+            None => Err("Unexpected end of the expression".into()),
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, String> {
+        let mut literal = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            literal.push(c);
+        }
+        let literal = literal.replace('_', "");
+
+        let result = match literal
+            .strip_prefix("0x")
+            .or_else(|| literal.strip_prefix("0X"))
+        {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => literal.parse(),
+        };
+        result.map_err(|_| format!("Invalid number '{}'", literal))
+    }
+}
+
+#[test]
+fn evaluate_expressions() {
+    assert_eq!(evaluate("640"), Ok(640));
+    assert_eq!(evaluate("0x280"), Ok(640));
+    assert_eq!(evaluate("16*40"), Ok(640));
+    assert_eq!(evaluate(" 2 + 3 * 4 "), Ok(14));
+    assert_eq!(evaluate("(2 + 3) * 4"), Ok(20));
+    assert_eq!(evaluate("10 - 2 - 3"), Ok(5));
+    assert_eq!(evaluate("-0x10 + 1_000"), Ok(984));
+    assert!(evaluate("2 +").is_err());
+    assert!(evaluate("(2").is_err());
+    assert!(evaluate("0xfg").is_err());
+    assert!(evaluate("2 3").is_err());
+}
@@ -1,17 +1,33 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use clap::ArgEnum;
 use egui::{ClippedMesh, FontDefinitions};
 use egui_wgpu_backend::{BackendError, RenderPass, ScreenDescriptor};
 use egui_winit_platform::{Platform, PlatformDescriptor};
+use epi::TextureAllocator;
 use humansize::{file_size_opts, FileSize};
 use pixels::{wgpu, PixelsContext};
-use winit::window::Window;
+use winit::{
+    dpi::PhysicalSize,
+    event::{Event, WindowEvent},
+    window::{Window, WindowId},
+};
 
 use crate::{
     datatype::{Endianness, Signedness},
-    settings::{GuiDatatype, PixelStyle, Settings, HEIGHT},
+    event_loop::KEY_BINDINGS,
+    expression,
+    scaling::ScalingFilter,
+    settings::{
+        ColorScale, ColumnProfile, EntropyJump, FoldMode, GuiDatatype, Inspector, LoadChoice,
+        NibbleMode, PixelStyle, Settings, Sparklines, StrideSampling, LIKELY_IMAGE_CORRELATION,
+    },
+    style::{AlphaMode, CategoryRule, Palette},
 };
 
+/// How long an input field is highlighted after an invalid expression
+const INPUT_ERROR_DURATION: Duration = Duration::from_millis(600);
+
 pub struct Gui {
     // State for egui.
     start_time: Instant,
@@ -19,6 +35,22 @@ pub struct Gui {
     screen_descriptor: ScreenDescriptor,
     rpass: RenderPass,
     paint_jobs: Vec<ClippedMesh>,
+
+    /// Scale factor of the window, and the GUI scale from the settings which egui currently uses
+    window_scale_factor: f64,
+    gui_scale: f32,
+
+    export_preview: Option<(egui::TextureId, egui::Vec2)>,
+
+    /// Text of the width input field, and until when it is highlighted after an invalid input
+    width_input: String,
+    width_input_error: Option<Instant>,
+    /// The same for the input field of the offset to go to
+    goto_input: String,
+    goto_input_error: Option<Instant>,
+    /// Row and column of the current layout to go to
+    goto_row: i64,
+    goto_column: i64,
 }
 
 impl Gui {
@@ -43,11 +75,53 @@ impl Gui {
             screen_descriptor,
             rpass,
             paint_jobs: Vec::new(),
+            window_scale_factor: scale_factor,
+            gui_scale: 1.0,
+            export_preview: None,
+            width_input: String::new(),
+            width_input_error: None,
+            goto_input: String::new(),
+            goto_input_error: None,
+            goto_row: 0,
+            goto_column: 0,
         }
     }
 
-    pub fn handle_event(&mut self, event: &winit::event::Event<'_, ()>) {
-        self.platform.handle_event(event);
+    pub fn handle_event(&mut self, event: &Event<'_, ()>) {
+        match event {
+            Event::WindowEvent {
+                window_id,
+                event:
+                    WindowEvent::ScaleFactorChanged {
+                        scale_factor,
+                        new_inner_size,
+                    },
+            } => {
+                self.window_scale_factor = *scale_factor;
+                self.apply_scale_factor(*window_id, **new_inner_size);
+            }
+            _ => self.platform.handle_event(event),
+        }
+    }
+
+    /// Let egui use the scale factor of the window multiplied by the GUI scale
+    fn apply_scale_factor(&mut self, window_id: WindowId, size: PhysicalSize<u32>) {
+        let scale_factor = self.window_scale_factor * self.gui_scale as f64;
+        let mut size = size;
+        let event: Event<'_, ()> = Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size: &mut size,
+            },
+        };
+        self.platform.handle_event(&event);
+        self.screen_descriptor.scale_factor = scale_factor as f32;
+    }
+
+    /// Number of physical pixels per point of the GUI
+    pub fn pixels_per_point(&self) -> f32 {
+        self.screen_descriptor.scale_factor
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -57,11 +131,19 @@ impl Gui {
         }
     }
 
-    pub fn scale_factor(&mut self, scale_factor: f64) {
-        self.screen_descriptor.scale_factor = scale_factor as f32;
-    }
-
     pub fn prepare(&mut self, window: &Window, settings: &mut Settings) {
+        // Changing the scale while dragging the slider would move it under the mouse pointer.
+        if settings.gui_scale != self.gui_scale
+            && !self.platform.context().input().pointer.any_down()
+        {
+            self.gui_scale = settings.gui_scale;
+            let size = PhysicalSize::new(
+                self.screen_descriptor.physical_width,
+                self.screen_descriptor.physical_height,
+            );
+            self.apply_scale_factor(window.id(), size);
+        }
+
         self.platform
             .update_time(self.start_time.elapsed().as_secs_f64());
 
@@ -80,23 +162,87 @@ impl Gui {
     fn ui(&mut self, ctx: &egui::CtxRef, settings: &mut Settings) {
         let max_offset_fine = settings.max_offset_fine();
         let max_width = settings.max_width();
-        egui::SidePanel::right("Settings").show(ctx, |ui| {
+        let max_fold_period = settings.max_fold_period();
+        // The panel can be resized by dragging its left edge, the canvas takes the rest
+        let panel = egui::SidePanel::right("Settings")
+            .resizable(true)
+            .default_width(settings.panel_width);
+        let panel_response = panel.show(ctx, |ui| {
             ui.add(egui::Label::new("Layout").heading());
-            ui.add(
-                egui::Slider::new(
-                    &mut settings.zoom,
-                    settings.zoom_range.0..=settings.zoom_range.1,
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::Slider::new(
+                        &mut settings.zoom,
+                        settings.zoom_range.0..=settings.zoom_range.1,
+                    )
+                    .clamp_to_range(true)
+                    .smart_aim(false)
+                    .text("zoom"),
+                );
+                let fit_label = if settings.fit_restore.is_some() {
+                    "Restore"
+                } else {
+                    "Fit file"
+                };
+                if ui
+                    .button(fit_label)
+                    .on_hover_text("Show the whole file on the canvas (key: A)")
+                    .clicked()
+                {
+                    settings.toggle_fit_to_file();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("max render size:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.max_render_size)
+                        .clamp_range(256..=8192)
+                        .speed(16.0),
                 )
-                .clamp_to_range(true)
-                .smart_aim(false)
-                .text("zoom"),
-            );
+                .on_hover_text("Larger windows are rendered at a lower resolution and scaled up");
+            });
+            ui.horizontal(|ui| {
+                ui.label("scaling:");
+                ui.selectable_value(
+                    &mut settings.scaling_filter,
+                    ScalingFilter::Nearest,
+                    "Nearest",
+                );
+                ui.selectable_value(
+                    &mut settings.scaling_filter,
+                    ScalingFilter::Linear,
+                    "Linear",
+                );
+            });
+            let width_suffix = hex_suffix(settings.width as i64);
             ui.add(
                 egui::Slider::new(&mut settings.width, 1..=max_width)
                     .clamp_to_range(true)
                     .smart_aim(false)
+                    .suffix(width_suffix)
                     .text("width"),
             );
+            ui.horizontal(|ui| {
+                let error_visible = self
+                    .width_input_error
+                    .map_or(false, |time| time.elapsed() < INPUT_ERROR_DURATION);
+                let mut input = egui::TextEdit::singleline(&mut self.width_input)
+                    .hint_text("e.g. 0x280 or 16*40")
+                    .desired_width(100.0);
+                if error_visible {
+                    input = input.text_color(egui::Color32::RED);
+                }
+                let response = ui.add(input);
+                if response.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+                    match expression::evaluate(&self.width_input) {
+                        Ok(width) if (1..=max_width as i64).contains(&width) => {
+                            settings.width = width as isize;
+                            self.width_input_error = None;
+                        }
+                        _ => self.width_input_error = Some(Instant::now()),
+                    }
+                }
+            });
             ui.horizontal(|ui| {
                 if ui
                     .add_enabled(settings.width % 2 == 0, egui::Button::new("÷ 2"))
@@ -126,30 +272,314 @@ impl Gui {
                     settings.width *= 2;
                 }
             });
+            let stride_suffix = hex_suffix(settings.stride as i64);
             ui.add(
                 egui::Slider::new(&mut settings.stride, 1..=settings.max_stride)
                     .clamp_to_range(true)
                     .smart_aim(false)
+                    .suffix(stride_suffix)
                     .text("stride"),
             );
+            ui.horizontal(|ui| {
+                ui.label("stride sampling:");
+                ui.add_enabled_ui(settings.stride > 1, |ui| {
+                    ui.selectable_value(
+                        &mut settings.stride_sampling,
+                        StrideSampling::Skip,
+                        "Skip",
+                    )
+                    .on_hover_text("Only the first byte of every step");
+                    ui.selectable_value(
+                        &mut settings.stride_sampling,
+                        StrideSampling::Mean,
+                        "Mean",
+                    )
+                    .on_hover_text("Average of the bytes of every step");
+                    ui.selectable_value(&mut settings.stride_sampling, StrideSampling::Max, "Max")
+                        .on_hover_text("Largest byte of every step, to spot rare markers");
+                });
+            });
+            if settings.skips_bytes() {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!(
+                            "⚠ showing {} of every {} bytes",
+                            settings.bytes_per_cell(),
+                            settings.stride
+                        ),
+                    );
+                    ui.checkbox(&mut settings.stride_hatch, "hatch")
+                        .on_hover_text("Mark the canvas while bytes are skipped");
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("nibbles:");
+                ui.selectable_value(&mut settings.nibble_mode, NibbleMode::Off, "Off");
+                ui.selectable_value(
+                    &mut settings.nibble_mode,
+                    NibbleMode::HighFirst,
+                    "High first",
+                );
+                ui.selectable_value(&mut settings.nibble_mode, NibbleMode::LowFirst, "Low first");
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.bit_mask.enabled, "bit mask");
+                ui.add_enabled_ui(settings.bit_mask.enabled, |ui| {
+                    let mask_suffix = format!(" ({:08b})", settings.bit_mask.mask);
+                    ui.add(
+                        egui::DragValue::new(&mut settings.bit_mask.mask)
+                            .prefix("mask: ")
+                            .suffix(mask_suffix),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut settings.bit_mask.shift)
+                            .clamp_range(0..=7)
+                            .prefix("shift: "),
+                    );
+                    if ui
+                        .button("next bit")
+                        .on_hover_text("Show the next lower single bit")
+                        .clicked()
+                    {
+                        let shift = (settings.bit_mask.shift + 7) % 8;
+                        settings.bit_mask.shift = shift;
+                        settings.bit_mask.mask = 1 << shift;
+                    }
+                });
+            });
+            ui.horizontal(|ui| {
+                let contrast = &mut settings.contrast;
+                ui.checkbox(&mut contrast.enabled, "auto contrast")
+                    .on_hover_text("Stretch the range of byte values in the view to all colors");
+                ui.add_enabled_ui(contrast.enabled, |ui| {
+                    ui.checkbox(&mut contrast.locked, "lock")
+                        .on_hover_text("Keep the range when the view moves");
+                    ui.label(format!(
+                        "range: {:02x} - {:02x}",
+                        contrast.min, contrast.max
+                    ));
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.split_view, "split view");
+                ui.add_enabled_ui(settings.split_view, |ui| {
+                    ui.label("delta:");
+                    let delta_suffix = hex_suffix(settings.split_delta);
+                    ui.add(
+                        egui::DragValue::new(&mut settings.split_delta)
+                            .clamp_range(0..=settings.buffer_length)
+                            .suffix(delta_suffix),
+                    );
+                    if ui.button("½ file").clicked() {
+                        settings.split_delta = settings.buffer_length / 2;
+                    }
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.fold.enabled, "fold");
+                ui.add_enabled_ui(settings.fold.enabled, |ui| {
+                    ui.label("period:");
+                    let period_suffix = hex_suffix(settings.fold.period);
+                    ui.add(
+                        egui::DragValue::new(&mut settings.fold.period)
+                            .clamp_range(1..=max_fold_period)
+                            .suffix(period_suffix),
+                    );
+                    ui.label("copies:");
+                    ui.add(egui::DragValue::new(&mut settings.fold.copies).clamp_range(1..=4096));
+                    ui.selectable_value(&mut settings.fold.mode, FoldMode::Xor, "XOR");
+                    ui.selectable_value(&mut settings.fold.mode, FoldMode::Max, "Max");
+                });
+            });
+            ui.checkbox(&mut settings.reverse, "reverse")
+                .on_hover_text("Traverse the file from its end to its start");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.interleave.enabled, "interleaved")
+                    .on_hover_text("Show only one of several interleaved streams of samples");
+                ui.add_enabled_ui(settings.interleave.enabled, |ui| {
+                    let interleave = &mut settings.interleave;
+                    ui.label("streams:");
+                    ui.add(egui::DragValue::new(&mut interleave.streams).clamp_range(2..=256));
+                    ui.label("sample size:");
+                    ui.add(egui::DragValue::new(&mut interleave.sample_size).clamp_range(1..=256));
+                    ui.label("stream:");
+                    ui.add(
+                        egui::DragValue::new(&mut interleave.stream)
+                            .clamp_range(0..=interleave.streams - 1),
+                    );
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.record.enabled, "records");
+                ui.add_enabled_ui(settings.record.enabled, |ui| {
+                    ui.label("size:");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.record.size)
+                            .clamp_range(1..=settings.max_stride),
+                    );
+                    ui.label("field:");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.record.field)
+                            .clamp_range(0..=settings.record.size - 1),
+                    );
+                    if ui
+                        .button("Isolate")
+                        .on_hover_text("Show only this byte of every record")
+                        .clicked()
+                    {
+                        settings.isolate_field();
+                    }
+                    ui.checkbox(&mut settings.sparklines.visible, "sparklines")
+                        .on_hover_text("Plot every byte of a record across the visible records");
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.profile.enabled, "profile view")
+                    .on_hover_text("Plot the value of each byte against its offset");
+                ui.add_enabled(
+                    settings.profile.enabled,
+                    egui::Checkbox::new(&mut settings.profile.signed, "signed"),
+                );
+            });
             ui.separator();
 
             ui.add(egui::Label::new("Offset").heading());
+            // The coarse slider uses a logarithmic scale to cover the whole file. While shift is
+            // held, dragging only moves the offset a single byte towards the pointer per step.
+            let fine_drag = ui.input().modifiers.shift;
+            let offset_suffix = hex_suffix(settings.offset);
+            let offset = &mut settings.offset;
             ui.add(
-                egui::Slider::new(&mut settings.offset, 0..=settings.buffer_length)
-                    .clamp_to_range(true)
-                    .smart_aim(false)
-                    .text("coarse"),
-            );
+                egui::Slider::from_get_set(0.0..=(settings.buffer_length as f64), |value| {
+                    if let Some(value) = value {
+                        let target = value.round() as i64;
+                        *offset = if fine_drag {
+                            *offset + (target - *offset).signum()
+                        } else {
+                            target
+                        };
+                    }
+                    *offset as f64
+                })
+                .integer()
+                .logarithmic(true)
+                .smallest_positive(1.0)
+                .clamp_to_range(true)
+                .smart_aim(false)
+                .suffix(offset_suffix)
+                .text("coarse"),
+            )
+            .on_hover_text("Hold shift to move byte by byte");
+            let fine_suffix = hex_suffix(settings.offset_fine);
             ui.add(
                 egui::Slider::new(&mut settings.offset_fine, 0..=max_offset_fine)
-                    .clamp_to_range(true)
+                    // Dragging the value past the end of the range carries into the coarse offset
+                    .clamp_to_range(false)
                     .smart_aim(false)
+                    .suffix(fine_suffix)
                     .text("fine"),
             );
+            ui.horizontal(|ui| {
+                ui.label("go to:");
+                let error_visible = self
+                    .goto_input_error
+                    .map_or(false, |time| time.elapsed() < INPUT_ERROR_DURATION);
+                let mut input = egui::TextEdit::singleline(&mut self.goto_input)
+                    .hint_text(if settings.show_addresses {
+                        "address"
+                    } else {
+                        "offset"
+                    })
+                    .desired_width(100.0);
+                if error_visible {
+                    input = input.text_color(egui::Color32::RED);
+                }
+                let response = ui.add(input).on_hover_text(
+                    "An expression like 0x1000+4*16, which is a virtual address if addresses \
+                     are shown",
+                );
+                if response.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+                    let offset = expression::evaluate(&self.goto_input).map(|value| {
+                        if settings.show_addresses {
+                            value.wrapping_sub(settings.address_base)
+                        } else {
+                            value
+                        }
+                    });
+                    match offset {
+                        Ok(offset) if (0..=settings.buffer_length).contains(&offset) => {
+                            settings.offset = offset;
+                            settings.offset_fine = 0;
+                            self.goto_input_error = None;
+                        }
+                        _ => self.goto_input_error = Some(Instant::now()),
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("go to row:");
+                ui.add(egui::DragValue::new(&mut self.goto_row).clamp_range(0..=i64::MAX));
+                ui.label("column:");
+                ui.add(egui::DragValue::new(&mut self.goto_column).clamp_range(0..=i64::MAX));
+                if ui
+                    .button("Go")
+                    .on_hover_text(
+                        "Scroll to the row of the current layout and select the byte in the \
+                         column. Row 0 is the first row of the file.",
+                    )
+                    .clicked()
+                    && !settings.go_to_row_column(self.goto_row, self.goto_column)
+                {
+                    settings.status = format!(
+                        "Row {}, column {} is outside of the grid or the file",
+                        self.goto_row, self.goto_column
+                    );
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.show_addresses, "addresses")
+                    .on_hover_text("Show virtual addresses (base + offset) instead of offsets");
+                ui.label("base:");
+                let base_suffix = hex_suffix(settings.address_base);
+                ui.add(
+                    egui::DragValue::new(&mut settings.address_base)
+                        .speed(4096.0)
+                        .suffix(base_suffix),
+                );
+            });
             ui.separator();
 
-            ui.add(egui::Label::new("Pixel style").heading());
+            ui.add(egui::Label::new("Search").heading());
+            ui.horizontal(|ui| {
+                let search = &mut settings.search;
+                let response = ui.text_edit_singleline(&mut search.query).on_hover_text(
+                    "Hexadecimal bytes like 'de ad be ef', or text in double quotes",
+                );
+                if ui.button("Find").clicked()
+                    || (response.lost_focus() && ui.input().key_pressed(egui::Key::Enter))
+                {
+                    search.search_requested = true;
+                }
+                if ui
+                    .add_enabled(!search.matches.is_empty(), egui::Button::new("Next"))
+                    .clicked()
+                {
+                    search.next_requested = true;
+                }
+            });
+            if !settings.search.status.is_empty() {
+                let position = settings
+                    .search
+                    .current
+                    .map(|i| format!("match {} of ", i + 1))
+                    .unwrap_or_default();
+                ui.label(format!("{}{}", position, settings.search.status));
+            }
+            ui.separator();
+
+            ui.add(egui::Label::new("Pixel style").heading())
+                .on_hover_text("Key: S switches to the previously used style");
             ui.label("Single byte");
             ui.horizontal_wrapped(|ui| {
                 ui.selectable_value(&mut settings.pixel_style, PixelStyle::Colorful, "Default");
@@ -189,12 +619,71 @@ impl Gui {
                     PixelStyle::GradientCubehelix,
                     "Cubehelix",
                 );
+                ui.selectable_value(&mut settings.pixel_style, PixelStyle::Delta, "Delta");
+                ui.selectable_value(&mut settings.pixel_style, PixelStyle::Position, "Position");
+                ui.selectable_value(
+                    &mut settings.pixel_style,
+                    PixelStyle::RunLength,
+                    "Run length",
+                );
+                ui.selectable_value(&mut settings.pixel_style, PixelStyle::Relief, "Relief")
+                    .on_hover_text("Byte values as the heights of a shaded terrain");
                 ui.selectable_value(
                     &mut settings.pixel_style,
                     PixelStyle::Entropy,
                     "Entropy (slow)",
                 );
             });
+            if !settings.color_scale.colors.is_empty() {
+                Self::color_scale(ui, &settings.color_scale);
+            }
+
+            ui.horizontal(|ui| {
+                ui.set_enabled(matches!(
+                    settings.pixel_style,
+                    PixelStyle::Colorful | PixelStyle::Category
+                ));
+                ui.label("palette:");
+                ui.selectable_value(
+                    &mut settings.style_settings.palette,
+                    Palette::Standard,
+                    "Standard",
+                );
+                ui.selectable_value(
+                    &mut settings.style_settings.palette,
+                    Palette::ColorBlindSafe,
+                    "Color-blind safe",
+                );
+            });
+            ui.add_enabled_ui(settings.pixel_style == PixelStyle::Category, |ui| {
+                ui.checkbox(
+                    &mut settings.style_settings.category_shade_other,
+                    "shade non-ASCII bytes by value",
+                );
+                Self::category_rules(ui, &mut settings.style_settings.category_rules);
+            });
+
+            ui.add_enabled(
+                matches!(
+                    settings.pixel_style,
+                    PixelStyle::GradientMagma
+                        | PixelStyle::GradientPlasma
+                        | PixelStyle::GradientViridis
+                        | PixelStyle::GradientRainbow
+                        | PixelStyle::GradientTurbo
+                        | PixelStyle::GradientCubehelix
+                ),
+                egui::Checkbox::new(
+                    &mut settings.style_settings.reverse_gradient,
+                    "reverse gradient",
+                ),
+            );
+            ui.add_enabled(
+                settings.pixel_style == PixelStyle::Grayscale,
+                egui::Slider::new(&mut settings.style_settings.grayscale_gamma, 0.2..=5.0)
+                    .logarithmic(true)
+                    .text("gamma"),
+            );
 
             ui.label("Multi-byte");
             ui.horizontal(|ui| {
@@ -203,7 +692,72 @@ impl Gui {
                 ui.selectable_value(&mut settings.pixel_style, PixelStyle::Rgb, "RGB");
                 ui.selectable_value(&mut settings.pixel_style, PixelStyle::Bgr, "BGR");
             });
-            ui.selectable_value(&mut settings.pixel_style, PixelStyle::Datatype, "Datatype");
+            ui.horizontal(|ui| {
+                ui.set_enabled(matches!(
+                    settings.pixel_style,
+                    PixelStyle::Rgba | PixelStyle::Abgr
+                ));
+                ui.label("alpha:");
+                ui.selectable_value(
+                    &mut settings.style_settings.alpha_mode,
+                    AlphaMode::Opaque,
+                    "Ignore",
+                );
+                ui.selectable_value(
+                    &mut settings.style_settings.alpha_mode,
+                    AlphaMode::Checkerboard,
+                    "Checkerboard",
+                );
+                ui.selectable_value(
+                    &mut settings.style_settings.alpha_mode,
+                    AlphaMode::Alpha,
+                    "Alpha only",
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut settings.pixel_style, PixelStyle::Pointer, "Pointers");
+                ui.add_enabled_ui(settings.pixel_style == PixelStyle::Pointer, |ui| {
+                    let pointer = &mut settings.style_settings.pointer;
+                    ui.selectable_value(&mut pointer.word_size, 4, "32 bit");
+                    ui.selectable_value(&mut pointer.word_size, 8, "64 bit");
+                    ui.selectable_value(&mut pointer.endianness, Endianness::Little, "LE");
+                    ui.selectable_value(&mut pointer.endianness, Endianness::Big, "BE");
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.set_enabled(settings.pixel_style == PixelStyle::Pointer);
+                let pointer = &mut settings.style_settings.pointer;
+                ui.label("base:");
+                let base_suffix = hex_suffix(pointer.base as i64);
+                ui.add(
+                    egui::DragValue::new(&mut pointer.base)
+                        .speed(4096.0)
+                        .suffix(base_suffix),
+                );
+                ui.label("size:");
+                let size_suffix = hex_suffix(pointer.size as i64);
+                ui.add(
+                    egui::DragValue::new(&mut pointer.size)
+                        .speed(4096.0)
+                        .suffix(size_suffix),
+                )
+                .on_hover_text("Zero for the size of the file");
+            });
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut settings.pixel_style, PixelStyle::Datatype, "Datatype");
+                if ui
+                    .button("Signed bytes")
+                    .on_hover_text("Datatype style with signed 8 bit integers, centered at zero")
+                    .clicked()
+                {
+                    let datatype = &mut settings.style_settings.datatype;
+                    datatype.datatype = GuiDatatype::Integer8;
+                    datatype.signedness = Signedness::Signed;
+                    datatype.value_range = (-128.0, 127.0);
+                    datatype.diverging = true;
+                    settings.pixel_style = PixelStyle::Datatype;
+                }
+            });
             ui.separator();
             ui.label("Datatype");
             ui.vertical(|ui| {
@@ -211,32 +765,32 @@ impl Gui {
 
                 ui.horizontal_wrapped(|ui| {
                     ui.selectable_value(
-                        &mut settings.datatype_settings.datatype,
+                        &mut settings.style_settings.datatype.datatype,
                         GuiDatatype::Integer8,
                         "Integer (8 bit)",
                     );
                     ui.selectable_value(
-                        &mut settings.datatype_settings.datatype,
+                        &mut settings.style_settings.datatype.datatype,
                         GuiDatatype::Integer16,
                         "Integer (16 bit)",
                     );
                     ui.selectable_value(
-                        &mut settings.datatype_settings.datatype,
+                        &mut settings.style_settings.datatype.datatype,
                         GuiDatatype::Integer32,
                         "Integer (32 bit)",
                     );
                     ui.selectable_value(
-                        &mut settings.datatype_settings.datatype,
+                        &mut settings.style_settings.datatype.datatype,
                         GuiDatatype::Integer64,
                         "Integer (64 bit)",
                     );
                     ui.selectable_value(
-                        &mut settings.datatype_settings.datatype,
+                        &mut settings.style_settings.datatype.datatype,
                         GuiDatatype::Float32,
                         "Float (32 bit)",
                     );
                     ui.selectable_value(
-                        &mut settings.datatype_settings.datatype,
+                        &mut settings.style_settings.datatype.datatype,
                         GuiDatatype::Float64,
                         "Float (64 bit)",
                     );
@@ -244,7 +798,7 @@ impl Gui {
                 ui.label("Signedness");
                 ui.horizontal(|ui| {
                     // Only enable for datatypes that have 'signedness'
-                    ui.set_enabled(match settings.datatype_settings.datatype {
+                    ui.set_enabled(match settings.style_settings.datatype.datatype {
                         GuiDatatype::Integer8
                         | GuiDatatype::Integer16
                         | GuiDatatype::Integer32
@@ -252,12 +806,12 @@ impl Gui {
                         GuiDatatype::Float32 | GuiDatatype::Float64 => false,
                     });
                     ui.selectable_value(
-                        &mut settings.datatype_settings.signedness,
+                        &mut settings.style_settings.datatype.signedness,
                         Signedness::Unsigned,
                         "Unsigned",
                     );
                     ui.selectable_value(
-                        &mut settings.datatype_settings.signedness,
+                        &mut settings.style_settings.datatype.signedness,
                         Signedness::Signed,
                         "Signed",
                     );
@@ -265,7 +819,7 @@ impl Gui {
                 ui.label("Endianness");
                 ui.horizontal(|ui| {
                     // Only enable for datatypes that are multi-byte
-                    ui.set_enabled(match settings.datatype_settings.datatype {
+                    ui.set_enabled(match settings.style_settings.datatype.datatype {
                         GuiDatatype::Integer8 => false,
                         GuiDatatype::Integer16
                         | GuiDatatype::Integer32
@@ -274,12 +828,12 @@ impl Gui {
                         | GuiDatatype::Float64 => true,
                     });
                     ui.selectable_value(
-                        &mut settings.datatype_settings.endianness,
+                        &mut settings.style_settings.datatype.endianness,
                         Endianness::Little,
                         "Little Endian",
                     );
                     ui.selectable_value(
-                        &mut settings.datatype_settings.endianness,
+                        &mut settings.style_settings.datatype.endianness,
                         Endianness::Big,
                         "Big Endian",
                     );
@@ -287,15 +841,197 @@ impl Gui {
                 ui.label("");
                 ui.horizontal(|ui| {
                     ui.label("min:");
-                    ui.add(egui::DragValue::new(&mut settings.value_range.0).speed(10.0));
+                    ui.add(
+                        egui::DragValue::new(&mut settings.style_settings.datatype.value_range.0)
+                            .speed(10.0),
+                    );
                     ui.label("max:");
-                    ui.add(egui::DragValue::new(&mut settings.value_range.1).speed(10.0));
+                    ui.add(
+                        egui::DragValue::new(&mut settings.style_settings.datatype.value_range.1)
+                            .speed(10.0),
+                    );
+                    ui.checkbox(&mut settings.style_settings.datatype.diverging, "diverging")
+                        .on_hover_text(
+                            "Blue for negative values, white for zero, red for positive",
+                        );
                 });
             });
 
             ui.separator();
 
-            ui.checkbox(&mut settings.hex_view_visible, "hex view");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.compare.enabled, "compare styles");
+                ui.add_enabled(
+                    settings.compare.enabled,
+                    egui::DragValue::new(&mut settings.compare.count)
+                        .clamp_range(2..=4)
+                        .suffix(" tiles"),
+                );
+            });
+            if settings.compare.enabled {
+                ui.horizontal_wrapped(|ui| {
+                    let count = settings.compare.styles().len();
+                    for (i, tile_style) in settings.compare.styles[..count].iter_mut().enumerate() {
+                        egui::ComboBox::from_id_source(("compare style", i))
+                            .selected_text(tile_style.name())
+                            .show_ui(ui, |ui| {
+                                for &pixel_style in PixelStyle::value_variants() {
+                                    ui.selectable_value(
+                                        tile_style,
+                                        pixel_style,
+                                        pixel_style.name(),
+                                    );
+                                }
+                            });
+                    }
+                });
+            }
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.hex_view_visible, "hex view");
+                ui.checkbox(&mut settings.column_profile.visible, "column profile");
+                ui.checkbox(&mut settings.row_graph.visible, "row graph")
+                    .on_hover_text("Values of the bytes in the row under the cursor");
+                ui.checkbox(&mut settings.inspector.visible, "inspector")
+                    .on_hover_text("Values of the selected bytes as integers, floats and text");
+                ui.checkbox(&mut settings.regions.visible, "regions")
+                    .on_hover_text("Labeled ranges of the file, stored next to it");
+                ui.checkbox(&mut settings.spectrum.visible, "spectrum");
+                ui.checkbox(&mut settings.entropy_timeline.visible, "timeline")
+                    .on_hover_text("Entropy of the whole file, click to jump");
+                ui.checkbox(&mut settings.width_scores.visible, "widths")
+                    .on_hover_text("Rank widths by how coherent the columns are");
+                ui.checkbox(&mut settings.crosshair_visible, "crosshair");
+                ui.checkbox(&mut settings.text_overlay, "text");
+                if ui.button("Export PNG…").clicked() {
+                    settings.export.dialog_visible = !settings.export.dialog_visible;
+                }
+            });
+            ui.checkbox(&mut settings.tile_data, "tile the data")
+                .on_hover_text("Repeat the data past its end instead of leaving the area empty");
+            ui.horizontal(|ui| {
+                let view_center =
+                    settings.view_start() as f64 + settings.page_length() as f64 / 2.0;
+                let positional = settings.pixel_style.is_positional();
+                let mirror = &mut settings.mirror;
+                if ui
+                    .add_enabled(
+                        !positional,
+                        egui::Checkbox::new(&mut mirror.enabled, "mirror"),
+                    )
+                    .on_hover_text("Blend the data mirrored around an axis over the view")
+                    .on_disabled_hover_text(
+                        "The style colors bytes by their place in the data, not the mirrored one",
+                    )
+                    .changed()
+                    && mirror.enabled
+                {
+                    mirror.axis = view_center;
+                }
+                ui.add_enabled_ui(mirror.enabled && !positional, |ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut mirror.alpha)
+                            .clamp_range(0.0..=1.0)
+                            .speed(0.01)
+                            .prefix("alpha: "),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut mirror.axis)
+                            .speed(0.5)
+                            .max_decimals(1)
+                            .prefix("axis: "),
+                    )
+                    .on_hover_text("File offset around which the data is mirrored");
+                    if ui.button("Center").clicked() {
+                        mirror.axis = view_center;
+                    }
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.label("labels:");
+                ui.checkbox(&mut settings.annotations.signatures, "signatures")
+                    .on_hover_text("Magic numbers of file formats like ELF, PNG or ZIP");
+                ui.checkbox(&mut settings.annotations.strings, "strings");
+            });
+            ui.add(
+                egui::Slider::new(&mut settings.gui_scale, 0.5..=3.0)
+                    .clamp_to_range(true)
+                    .text("GUI scale"),
+            )
+            .on_hover_text("Applied when the mouse button is released");
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("jump to entropy:");
+                if ui.button("Highest").on_hover_text("Key: E").clicked() {
+                    settings.entropy_jump_requested = Some(EntropyJump::Highest);
+                }
+                if ui.button("Lowest").on_hover_text("Key: Shift+E").clicked() {
+                    settings.entropy_jump_requested = Some(EntropyJump::Lowest);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("editor:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut settings.editor_command).desired_width(160.0),
+                )
+                .on_hover_text("Placeholders: {file}, {offset}");
+                if ui
+                    .add_enabled(settings.versions.file_backed, egui::Button::new("Open"))
+                    .on_hover_text("Key: O")
+                    .clicked()
+                {
+                    settings.editor_requested = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(settings.versions.file_backed, egui::Button::new("Reload"))
+                    .on_hover_text("Read the file again, key: F5")
+                    .clicked()
+                {
+                    settings.reload_requested = true;
+                }
+                if ui
+                    .button("Load from clipboard")
+                    .on_hover_text("Hexadecimal bytes or raw data, key: Ctrl+V")
+                    .clicked()
+                {
+                    settings.clipboard_requested = true;
+                }
+                if ui
+                    .button("Copy view")
+                    .on_hover_text("Copy the view as an image, key: Ctrl+C")
+                    .clicked()
+                {
+                    settings.copy_image_requested = true;
+                }
+            });
+            ui.checkbox(&mut settings.keep_layout, "keep layout for dropped files")
+                .on_hover_text(
+                    "Show files dropped on the window with the current width, stride and offset \
+                     instead of their own",
+                );
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Pin row")
+                    .on_hover_text("Keep the first row of the view at the top, key: P")
+                    .clicked()
+                {
+                    settings.pin_row_requested = true;
+                }
+                if ui
+                    .add_enabled(settings.pinned_row.is_some(), egui::Button::new("Unpin"))
+                    .on_hover_text("Key: Shift+P")
+                    .clicked()
+                {
+                    settings.pinned_row = None;
+                }
+                if let Some(pinned) = &settings.pinned_row {
+                    ui.label(format!("at {}", settings.display_offset(pinned.offset)));
+                }
+            });
             ui.separator();
 
             ui.add(egui::Label::new("Information").heading());
@@ -304,15 +1040,175 @@ impl Gui {
                 .file_size(file_size_opts::BINARY)
                 .unwrap();
             ui.label(format!("file size: {}", file_size));
+            ui.horizontal(|ui| {
+                let padding = &mut settings.padding;
+                ui.checkbox(&mut padding.enabled, "trim padding")
+                    .on_hover_text("Hide the run of this byte at the end of the file");
+                let byte_suffix = hex_suffix(padding.byte as i64);
+                ui.add_enabled(
+                    padding.enabled,
+                    egui::DragValue::new(&mut padding.byte).suffix(byte_suffix),
+                );
+                if padding.enabled {
+                    ui.label(format!("{} bytes hidden", padding.trimmed));
+                }
+            });
+            if let Some(correlation) = settings.row_correlation {
+                ui.horizontal(|ui| {
+                    let likely_image = correlation >= LIKELY_IMAGE_CORRELATION;
+                    ui.label(format!(
+                        "row correlation: {:.2}{}",
+                        correlation,
+                        if likely_image { " (likely image)" } else { "" }
+                    ))
+                    .on_hover_text("How similar the visible rows are to the rows below them");
+                    if likely_image {
+                        if ui
+                            .button("Grayscale")
+                            .on_hover_text("Show the bytes as gray values")
+                            .clicked()
+                        {
+                            settings.pixel_style = PixelStyle::Grayscale;
+                        }
+                        if ui
+                            .button("RGB")
+                            .on_hover_text("Show every three bytes as a colored pixel")
+                            .clicked()
+                        {
+                            settings.pixel_style = PixelStyle::Rgb;
+                            if settings.stride == 1 {
+                                settings.stride = 3;
+                                settings.width = (settings.width / 3).max(1);
+                            }
+                        }
+                    }
+                });
+            }
+            if settings.versions.count > 1 {
+                ui.horizontal(|ui| {
+                    let versions = &mut settings.versions;
+                    if ui.button("◀").on_hover_text("Key: Shift+V").clicked() {
+                        versions.step_requested = Some(-1);
+                    }
+                    if ui.button("▶").on_hover_text("Key: V").clicked() {
+                        versions.step_requested = Some(1);
+                    }
+                    ui.label(format!(
+                        "version {} of {}",
+                        versions.current + 1,
+                        versions.count
+                    ))
+                    .on_hover_text(&versions.name);
+                    if let Some(base) = versions.detached_from {
+                        ui.label(format!("detached from 0x{:x}", base))
+                            .on_hover_text("Offset of the first byte in the original file");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let overlay = &mut settings.overlay;
+                    ui.checkbox(&mut overlay.enabled, "overlay next")
+                        .on_hover_text("Blend the next version over the shown one");
+                    ui.add_enabled_ui(overlay.enabled, |ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut overlay.alpha)
+                                .clamp_range(0.0..=1.0)
+                                .speed(0.01)
+                                .prefix("alpha: "),
+                        );
+                        ui.add(egui::DragValue::new(&mut overlay.delta).prefix("delta: "))
+                            .on_hover_text("Offset of the overlaid version, in bytes");
+                    });
+                });
+            }
+            if settings.hole_length > 0 {
+                ui.horizontal(|ui| {
+                    let hole_size = settings
+                        .hole_length
+                        .file_size(file_size_opts::BINARY)
+                        .unwrap();
+                    ui.label(format!("holes: {}", hole_size));
+                    ui.checkbox(&mut settings.show_holes, "show")
+                        .on_hover_text("Draw the holes like the area outside of the file");
+                });
+            }
             let zoom_factor = settings.zoom_factor();
-            let grid_size = (settings.width * (HEIGHT as isize) * settings.stride / zoom_factor)
+            let grid_size = (settings.width * settings.canvas_height * settings.stride
+                / zoom_factor)
                 .file_size(file_size_opts::BINARY)
                 .unwrap();
             ui.label(format!("grid size: {}", grid_size));
+            if let Some(grid) = settings.cursor_grid_position() {
+                let offset = settings.offset_at(&grid);
+                ui.label(format!(
+                    "cursor: column {}, row {}, {}",
+                    grid.column,
+                    grid.row,
+                    settings.display_offset(offset)
+                ));
+                if let Some((record, field_byte)) = settings.record_position(offset) {
+                    ui.label(format!("record {}, byte {}", record, field_byte));
+                }
+                if let Some(base) = settings.versions.detached_from {
+                    ui.label(format!("original offset: 0x{:x}", base + offset));
+                }
+            }
+            if let Some(selection) = settings.selection {
+                let range = selection.range();
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "selection: {} bytes from {}",
+                        range.end - range.start,
+                        settings.display_offset(range.start)
+                    ))
+                    .on_hover_text("Drag with Ctrl to select");
+                    if ui.button("Clear").clicked() {
+                        settings.selection = None;
+                    }
+                    if ui
+                        .button("Detach")
+                        .on_hover_text("Show the selected bytes as another version, from offset 0")
+                        .clicked()
+                    {
+                        settings.versions.detach_requested = true;
+                    }
+                });
+            }
         });
+        settings.panel_width = panel_response.response.rect.width();
+
+        if let Some(progress) = settings.analysis_progress {
+            egui::TopBottomPanel::bottom("analysis progress").show(ctx, |ui| {
+                ui.add(
+                    egui::ProgressBar::new(progress)
+                        .text(format!("Scanning the file… {:.0}%", progress * 100.0)),
+                );
+            });
+        }
+
+        if !settings.status.is_empty() {
+            egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+                ui.label(&settings.status);
+            });
+        }
 
         if settings.hex_view_visible {
             egui::TopBottomPanel::bottom("hex view").show(ctx, |ui| {
+                let transform = settings.transform_description();
+                ui.horizontal(|ui| {
+                    ui.add_enabled(
+                        transform.is_some(),
+                        egui::Checkbox::new(&mut settings.hex_view_transformed, "transformed"),
+                    );
+                    ui.label(match &transform {
+                        Some(transform) if settings.hex_view_transformed => {
+                            format!("Transformed bytes: {}", transform)
+                        }
+                        Some(transform) => {
+                            format!("Raw bytes (the view shows them after: {})", transform)
+                        }
+                        None => "Raw bytes".into(),
+                    });
+                });
                 ui.horizontal(|ui| {
                     ui.add(
                         egui::Label::new(&mut settings.hex_view)
@@ -328,10 +1224,653 @@ impl Gui {
             });
         }
 
+        if settings.row_graph.visible {
+            egui::TopBottomPanel::bottom("row graph").show(ctx, |ui| {
+                Self::row_graph(ui, settings);
+            });
+        }
+
+        if settings.inspector.visible {
+            egui::TopBottomPanel::bottom("inspector").show(ctx, |ui| {
+                Self::inspector(ui, &settings.inspector);
+            });
+        }
+
+        if settings.regions.visible {
+            egui::TopBottomPanel::bottom("regions").show(ctx, |ui| {
+                Self::regions(ui, settings);
+            });
+        }
+
+        if settings.column_profile.visible {
+            egui::TopBottomPanel::bottom("column profile").show(ctx, |ui| {
+                Self::column_profile(ui, &mut settings.column_profile);
+            });
+        }
+
+        if let Some(loading) = &mut settings.loading {
+            let title = if loading.download {
+                "Download"
+            } else {
+                "Large file"
+            };
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let file_size = loading.file_size.file_size(file_size_opts::BINARY).unwrap();
+                    let action = if loading.download {
+                        "Downloading"
+                    } else {
+                        "Loading"
+                    };
+                    if loading.started && loading.file_size == 0 {
+                        let bytes_read = loading.bytes_read.file_size(file_size_opts::BINARY);
+                        ui.add(egui::ProgressBar::new(0.0).text(format!(
+                            "{}… {}",
+                            action,
+                            bytes_read.unwrap()
+                        )));
+                    } else if loading.started {
+                        let progress = loading.bytes_read as f32 / loading.file_size.max(1) as f32;
+                        ui.add(egui::ProgressBar::new(progress).text(format!(
+                            "{}… {:.0}%",
+                            action,
+                            progress * 100.0
+                        )));
+                    } else if loading.download {
+                        if loading.file_size == 0 {
+                            ui.label("The size of the download is unknown.");
+                        } else {
+                            ui.label(format!("The download is {} large.", file_size));
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Download").clicked() {
+                                loading.choice = Some(LoadChoice::Read);
+                            }
+                            if ui.button("Cancel").clicked() {
+                                loading.choice = Some(LoadChoice::Cancel);
+                            }
+                        });
+                    } else {
+                        ui.label(format!(
+                            "The file is {} large. Reading it into memory can take a while.",
+                            file_size
+                        ));
+                        ui.horizontal(|ui| {
+                            if ui.button("Read into memory").clicked() {
+                                loading.choice = Some(LoadChoice::Read);
+                            }
+                            if ui
+                                .button("Map into memory")
+                                .on_hover_text("Opens the file instantly, like --backing mmap")
+                                .clicked()
+                            {
+                                loading.choice = Some(LoadChoice::Mmap);
+                            }
+                        });
+                    }
+                });
+        }
+
+        if settings.sparklines.visible && settings.record.enabled {
+            egui::TopBottomPanel::bottom("sparklines").show(ctx, |ui| {
+                Self::sparklines(ui, &settings.sparklines);
+            });
+        }
+
+        if settings.spectrum.visible {
+            egui::TopBottomPanel::bottom("spectrum").show(ctx, |ui| {
+                Self::spectrum(ui, settings);
+            });
+        }
+
+        if settings.entropy_timeline.visible {
+            egui::TopBottomPanel::bottom("entropy timeline").show(ctx, |ui| {
+                Self::entropy_timeline(ui, settings);
+            });
+        }
+
+        if settings.width_scores.visible {
+            egui::TopBottomPanel::bottom("width scores").show(ctx, |ui| {
+                Self::width_scores(ui, settings);
+            });
+        }
+
+        if settings.export.dialog_visible {
+            self.export_dialog(ctx, settings);
+        }
+
+        if settings.help_visible {
+            Self::help(ctx, settings);
+        }
+
         settings.gui_wants_keyboard = ctx.wants_keyboard_input();
         settings.gui_wants_mouse = ctx.wants_pointer_input();
     }
 
+    /// Table of custom colors for byte ranges in the category style
+    fn category_rules(ui: &mut egui::Ui, rules: &mut Vec<CategoryRule>) {
+        egui::CollapsingHeader::new(format!("Category rules ({})", rules.len()))
+            .id_source("category rules")
+            .show(ui, |ui| {
+                let mut removed = None;
+                for (i, rule) in rules.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label("bytes");
+                        let first_suffix = hex_suffix(rule.first as i64);
+                        ui.add(egui::DragValue::new(&mut rule.first).suffix(first_suffix));
+                        ui.label("to");
+                        let last_suffix = hex_suffix(rule.last as i64);
+                        ui.add(egui::DragValue::new(&mut rule.last).suffix(last_suffix));
+                        rule.last = rule.last.max(rule.first);
+                        ui.color_edit_button_srgb(&mut rule.color);
+                        if ui.button("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = removed {
+                    rules.remove(i);
+                }
+                if ui
+                    .button("Add rule")
+                    .on_hover_text("Rules take precedence over the built-in categories")
+                    .clicked()
+                {
+                    rules.push(CategoryRule {
+                        first: 0x1b,
+                        last: 0x1b,
+                        color: [255, 0, 255],
+                    });
+                }
+            });
+    }
+
+    fn inspector(ui: &mut egui::Ui, inspector: &Inspector) {
+        if inspector.interpretations.is_empty() {
+            ui.label("Select bytes by dragging with Ctrl to inspect them");
+            return;
+        }
+        egui::Grid::new("inspector").striped(true).show(ui, |ui| {
+            ui.label("");
+            ui.label("little endian");
+            ui.label("big endian");
+            ui.end_row();
+            for interpretation in &inspector.interpretations {
+                ui.label(interpretation.name);
+                ui.monospace(&interpretation.little);
+                ui.monospace(&interpretation.big);
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Regions in the order they were added, with a row to add the selection as another one
+    fn regions(ui: &mut egui::Ui, settings: &mut Settings) {
+        ui.horizontal(|ui| {
+            let regions = &mut settings.regions;
+            ui.add(egui::TextEdit::singleline(&mut regions.label).desired_width(120.0));
+            ui.color_edit_button_srgb(&mut regions.color);
+            if ui
+                .add_enabled(
+                    settings.selection.is_some(),
+                    egui::Button::new("Add selection"),
+                )
+                .on_hover_text("Label the selected bytes")
+                .clicked()
+            {
+                regions.add_requested = true;
+            }
+            let save = if regions.modified { "Save*" } else { "Save" };
+            if ui
+                .add_enabled(settings.versions.file_backed, egui::Button::new(save))
+                .on_hover_text(
+                    "Write the regions next to the file, they are loaded when it is opened",
+                )
+                .clicked()
+            {
+                regions.save_requested = true;
+            }
+        });
+
+        let mut removed = None;
+        let mut jump = None;
+        let mut modified = false;
+        egui::ScrollArea::vertical()
+            .max_height(120.0)
+            .show(ui, |ui| {
+                egui::Grid::new("regions").striped(true).show(ui, |ui| {
+                    for (i, region) in settings.regions.map.regions.iter_mut().enumerate() {
+                        modified |= ui.color_edit_button_srgb(&mut region.color).changed();
+                        modified |= ui
+                            .add(egui::TextEdit::singleline(&mut region.label).desired_width(120.0))
+                            .changed();
+                        ui.monospace(format!(
+                            "0x{:x}..0x{:x} ({} bytes)",
+                            region.start,
+                            region.end,
+                            region.end - region.start
+                        ));
+                        if ui.button("Go").clicked() {
+                            jump = Some(region.start);
+                        }
+                        if ui.button("✖").on_hover_text("Remove the region").clicked() {
+                            removed = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if let Some(i) = removed {
+            settings.regions.map.regions.remove(i);
+            modified = true;
+        }
+        settings.regions.modified |= modified;
+        if let Some(start) = jump {
+            settings.offset = settings.file_offset(start);
+            settings.offset_fine = 0;
+        }
+    }
+
+    /// Bar chart of the per-column deviation. Flat columns are likely constant or padding,
+    /// high bars are likely data. Clicking on a bar selects the column.
+    fn column_profile(ui: &mut egui::Ui, profile: &mut ColumnProfile) {
+        ui.label("Column profile (standard deviation per column, click to select)");
+
+        let desired_size = egui::vec2(ui.available_width(), 60.0);
+        let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
+        let painter = ui.painter_at(rect);
+
+        let columns = profile.deviation.len().max(1);
+        let column_width = rect.width() / columns as f32;
+        // The largest possible standard deviation of byte values is 127.5
+        for (column, deviation) in profile.deviation.iter().enumerate() {
+            let height = rect.height() * deviation / 127.5;
+            let left = rect.left() + column as f32 * column_width;
+            let color = if profile.selected_column == Some(column as isize) {
+                egui::Color32::from_rgb(249, 53, 94)
+            } else {
+                egui::Color32::from_rgb(60, 178, 255)
+            };
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(left, rect.bottom() - height),
+                    egui::pos2(left + column_width.max(1.0), rect.bottom()),
+                ),
+                0.0,
+                color,
+            );
+        }
+
+        if let Some(pos) = response.interact_pointer_pos() {
+            if response.clicked() {
+                profile.selected_column = Some(((pos.x - rect.left()) / column_width) as isize);
+            }
+        }
+
+        if let Some(column) = profile.selected_column {
+            let values: Vec<String> = profile
+                .selected_values
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            ui.add(
+                egui::Label::new(format!("column {}: {}", column, values.join(" ")))
+                    .monospace()
+                    .wrap(true),
+            );
+        }
+    }
+
+    /// Line plot of the bytes in the hovered row, with the byte under the cursor marked
+    fn row_graph(ui: &mut egui::Ui, settings: &Settings) {
+        let row_graph = &settings.row_graph;
+        if row_graph.values.is_empty() {
+            ui.label("Move the mouse over the canvas to plot the bytes of a row");
+            return;
+        }
+        ui.label(format!(
+            "Row at {}, byte {}: {}",
+            settings.display_offset(row_graph.offset),
+            row_graph.cursor_column,
+            row_graph
+                .values
+                .get(row_graph.cursor_column)
+                .map_or("--".into(), |b| format!("{:02x}", b))
+        ));
+
+        let desired_size = egui::vec2(ui.available_width(), 60.0);
+        let (rect, _) = ui.allocate_exact_size(desired_size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::from_gray(24));
+
+        let step = rect.width() / (row_graph.values.len().max(2) - 1) as f32;
+        let cursor_x = rect.left() + row_graph.cursor_column as f32 * step;
+        painter.line_segment(
+            [
+                egui::pos2(cursor_x, rect.top()),
+                egui::pos2(cursor_x, rect.bottom()),
+            ],
+            egui::Stroke::new(1.0, egui::Color32::from_rgb(249, 53, 94)),
+        );
+        let points = row_graph
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                egui::pos2(
+                    rect.left() + i as f32 * step,
+                    rect.bottom() - rect.height() * b as f32 / 255.0,
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 178, 255)),
+        ));
+    }
+
+    /// One small line plot per byte of a record, of its values across the visible records
+    fn sparklines(ui: &mut egui::Ui, sparklines: &Sparklines) {
+        let records = sparklines.values.first().map_or(0, Vec::len);
+        ui.label(format!("Bytes of {} visible records", records));
+        ui.horizontal_wrapped(|ui| {
+            for (byte, values) in sparklines.values.iter().enumerate() {
+                let (rect, response) =
+                    ui.allocate_exact_size(egui::vec2(64.0, 24.0), egui::Sense::hover());
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, egui::Color32::from_gray(24));
+
+                let step = rect.width() / (values.len().max(2) - 1) as f32;
+                let points = values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &b)| {
+                        egui::pos2(
+                            rect.left() + i as f32 * step,
+                            rect.bottom() - rect.height() * b as f32 / 255.0,
+                        )
+                    })
+                    .collect();
+                painter.add(egui::Shape::line(
+                    points,
+                    egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 178, 255)),
+                ));
+                response.on_hover_text(format!("byte {}", byte));
+            }
+        });
+    }
+
+    /// Bar chart of the magnitudes of the frequencies in the visible bytes, with the period of the
+    /// strongest one as a suggestion for the width
+    fn spectrum(ui: &mut egui::Ui, settings: &mut Settings) {
+        let (len, period) = (settings.spectrum.len, settings.spectrum.period);
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Spectrum of {} visible bytes (low to high frequencies)",
+                len
+            ));
+            if let Some(period) = period {
+                ui.label(format!("strongest period: {:.2}", period));
+                if ui.button("Use as width").clicked() {
+                    settings.width = period.round() as isize * settings.pixels_per_byte();
+                }
+            }
+        });
+
+        let spectrum = &settings.spectrum;
+        let desired_size = egui::vec2(ui.available_width(), 60.0);
+        let (rect, _) = ui.allocate_exact_size(desired_size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        // Every bar shows the maximum of the frequencies it covers
+        let bars = (rect.width() as usize).max(1);
+        let max = spectrum.magnitudes.iter().copied().fold(0.0, f32::max);
+        if max <= 0.0 {
+            return;
+        }
+        let per_bar = (spectrum.magnitudes.len() + bars - 1) / bars;
+        for (bar, chunk) in spectrum.magnitudes.chunks(per_bar.max(1)).enumerate() {
+            let magnitude = chunk.iter().copied().fold(0.0, f32::max);
+            let height = rect.height() * magnitude / max;
+            let left = rect.left() + bar as f32;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(left, rect.bottom() - height),
+                    egui::pos2(left + 1.0, rect.bottom()),
+                ),
+                0.0,
+                egui::Color32::from_rgb(60, 178, 255),
+            );
+        }
+    }
+
+    /// Bar with the colors of all byte values from 0x00 to 0xff, to read off the values of colors
+    fn color_scale(ui: &mut egui::Ui, color_scale: &ColorScale) {
+        let desired_size = egui::vec2(ui.available_width(), 12.0);
+        let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        let colors = &color_scale.colors;
+        let value_width = rect.width() / colors.len() as f32;
+        for (value, &[r, g, b, _]) in colors.iter().enumerate() {
+            let left = rect.left() + value as f32 * value_width;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(left, rect.top()),
+                    egui::pos2(left + value_width + 0.5, rect.bottom()),
+                ),
+                0.0,
+                egui::Color32::from_rgb(r, g, b),
+            );
+        }
+
+        if let Some(pos) = response.hover_pos() {
+            let value = (((pos.x - rect.left()) / value_width) as usize).min(colors.len() - 1);
+            response.on_hover_text(format!("{} (0x{:02x})", value, value));
+        }
+        ui.horizontal(|ui| {
+            ui.label("0x00");
+            ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                ui.label("0xff");
+            });
+        });
+    }
+
+    /// Entropy of the whole file from left to right, with the visible part marked. Clicking or
+    /// dragging on it moves the view there.
+    fn entropy_timeline(ui: &mut egui::Ui, settings: &mut Settings) {
+        let desired_size = egui::vec2(ui.available_width(), 24.0);
+        let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::from_gray(24));
+
+        let entropies = &settings.entropy_timeline.entropies;
+        let segment_width = rect.width() / entropies.len().max(1) as f32;
+        for (segment, &entropy) in entropies.iter().enumerate() {
+            let height = rect.height() * entropy;
+            let left = rect.left() + segment as f32 * segment_width;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(left, rect.bottom() - height),
+                    egui::pos2(left + segment_width.max(1.0), rect.bottom()),
+                ),
+                0.0,
+                egui::Color32::from_rgb(60, 178, 255),
+            );
+        }
+
+        let length = settings.buffer_length.max(1) as f32;
+        let x_at = |offset: i64| rect.left() + rect.width() * offset as f32 / length;
+        // In reverse, the positions of the traversal map back to file offsets the same way
+        let first = settings.file_offset(settings.view_start());
+        let last = settings.file_offset(settings.view_start() + settings.page_length());
+        let (start, end) = (first.min(last), first.max(last));
+        painter.rect_stroke(
+            egui::Rect::from_min_max(
+                egui::pos2(x_at(start), rect.top()),
+                egui::pos2(x_at(end).max(x_at(start) + 2.0), rect.bottom()),
+            ),
+            0.0,
+            egui::Stroke::new(1.0, egui::Color32::WHITE),
+        );
+
+        if let Some(pos) = response.interact_pointer_pos() {
+            if response.clicked() || response.dragged() {
+                let fraction = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                let offset = (fraction as f64 * settings.buffer_length as f64) as i64;
+                settings.offset = settings.file_offset(offset);
+                settings.offset_fine = 0;
+            }
+        }
+    }
+
+    /// The best candidate widths with their column coherence, each of which can be applied
+    fn width_scores(ui: &mut egui::Ui, settings: &mut Settings) {
+        let max_width = settings.max_width() / settings.pixels_per_byte();
+        let scores = &mut settings.width_scores;
+        ui.horizontal(|ui| {
+            ui.label("Widths ranked by column coherence, from");
+            ui.add(egui::DragValue::new(&mut scores.min_width).clamp_range(1..=scores.max_width));
+            ui.label("to");
+            ui.add(
+                egui::DragValue::new(&mut scores.max_width)
+                    .clamp_range(scores.min_width..=max_width),
+            );
+        });
+
+        let mut apply = None;
+        egui::Grid::new("width scores").show(ui, |ui| {
+            for &(width, score) in &scores.candidates {
+                ui.label(format!("{}{}", width, hex_suffix(width as i64)));
+                ui.add(egui::ProgressBar::new(score as f32).text(format!(
+                    "{:.1}% of the bytes equal the byte above",
+                    score * 100.0
+                )));
+                if ui.button("Apply").clicked() {
+                    apply = Some(width);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(width) = apply {
+            settings.width = width * settings.pixels_per_byte();
+        }
+    }
+
+    /// List of the key bindings on top of the dimmed canvas and panels
+    fn help(ctx: &egui::CtxRef, settings: &mut Settings) {
+        let layer = egui::LayerId::new(egui::Order::PanelResizeLine, egui::Id::new("help dim"));
+        ctx.layer_painter(layer).rect_filled(
+            ctx.input().screen_rect(),
+            0.0,
+            egui::Color32::from_black_alpha(160),
+        );
+
+        egui::Window::new("Key bindings")
+            .open(&mut settings.help_visible)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("key bindings")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (keys, action) in KEY_BINDINGS {
+                                    ui.add(egui::Label::new(*keys).monospace());
+                                    ui.label(*action);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+    }
+
+    fn export_dialog(&mut self, ctx: &egui::CtxRef, settings: &mut Settings) {
+        // Upload a freshly rendered preview to the GPU, replacing the previous one
+        if let Some(preview) = settings.export.preview.take() {
+            if let Some((texture_id, _)) = self.export_preview.take() {
+                self.rpass.free(texture_id);
+            }
+            let pixels: Vec<egui::Color32> = preview
+                .pixels
+                .chunks_exact(4)
+                .map(|p| egui::Color32::from_rgba_premultiplied(p[0], p[1], p[2], p[3]))
+                .collect();
+            let size = (preview.width as usize, preview.height as usize);
+            let texture_id = self.rpass.alloc_srgba_premultiplied(size, &pixels);
+            self.export_preview = Some((
+                texture_id,
+                egui::vec2(preview.width as f32, preview.height as f32),
+            ));
+        }
+
+        let zoom_factor = settings.zoom_factor();
+        let single_byte_style = settings.pixel_style.is_single_byte();
+        let export = &mut settings.export;
+        let mut open = export.dialog_visible;
+        egui::Window::new("Export PNG")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("file:");
+                    ui.text_edit_singleline(&mut export.path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("width:");
+                    ui.add(egui::DragValue::new(&mut export.width).clamp_range(1..=16384));
+                    ui.label("height:");
+                    ui.add(egui::DragValue::new(&mut export.height).clamp_range(1..=16384));
+                    ui.label("scale:");
+                    ui.add(egui::DragValue::new(&mut export.scale).clamp_range(1..=16));
+                });
+                ui.checkbox(
+                    &mut export.caption,
+                    "Caption with file name, offsets and palette",
+                );
+                ui.label(format!(
+                    "output: {} × {} pixels, {} × {} per byte",
+                    export.width,
+                    export.height,
+                    zoom_factor * export.scale as isize,
+                    zoom_factor * export.scale as isize,
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Preview").clicked() {
+                        export.preview_requested = true;
+                    }
+                    if ui.button("Save").clicked() {
+                        export.save_requested = true;
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("palette:");
+                    ui.text_edit_singleline(&mut export.palette_path);
+                    if ui
+                        .add_enabled(single_byte_style, egui::Button::new("Save palette"))
+                        .on_hover_text("Colors of all byte values, as .gpl or .csv")
+                        .clicked()
+                    {
+                        export.palette_requested = true;
+                    }
+                });
+                if !export.status.is_empty() {
+                    ui.label(&export.status);
+                }
+                if let Some((texture_id, size)) = self.export_preview {
+                    ui.image(texture_id, size);
+                }
+            });
+        settings.export.dialog_visible = open;
+    }
+
     /// Render egui.
     pub fn render(
         &mut self,
@@ -364,3 +1903,12 @@ impl Gui {
         )
     }
 }
+
+/// Shows the hexadecimal equivalent after a decimal value, e.g. '4160 (0x1040)'
+fn hex_suffix(value: i64) -> String {
+    if value < 0 {
+        format!(" (-0x{:x})", value.unsigned_abs())
+    } else {
+        format!(" (0x{:x})", value)
+    }
+}
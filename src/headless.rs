@@ -0,0 +1,93 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::binocle::Binocle;
+use crate::options::CliOptions;
+use crate::settings::{HEIGHT, WIDTH};
+
+/// Draw the on-screen canvas (including overlays like search matches) into a new RGBA buffer
+/// of the canvas size given in the settings
+pub fn render_to_vec(binocle: &Binocle) -> Vec<u8> {
+    let settings = &binocle.settings;
+    let mut frame = vec![0; (settings.canvas_width * settings.canvas_height * 4) as usize];
+    binocle.draw(&mut frame);
+    frame
+}
+
+/// Render a single frame with the given settings and print a hash of the pixel data. This is
+/// used to detect unintended changes to the rendering in regression tests.
+pub fn print_render_hash(options: CliOptions) -> Result<()> {
+    let binocle = Binocle::new(options, false)?;
+    let image = binocle.render(WIDTH, HEIGHT, 1);
+    print_output(&blake3::hash(&image.pixels).to_string())
+}
+
+/// Print the summary of the whole file, either human readable or as JSON
+pub fn print_analysis(options: CliOptions) -> Result<()> {
+    let json = options.json;
+    let binocle = Binocle::new(options, false)?;
+    let report = binocle.analyze();
+    if json {
+        print_output(&serde_json::to_string_pretty(&report)?)
+    } else {
+        print_output(&report.summary())
+    }
+}
+
+/// Print a line to stdout. A reader which stopped early (like 'head') is not an error.
+fn print_output(text: &str) -> Result<()> {
+    match writeln!(io::stdout().lock(), "{}", text) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Number of images of a filmstrip, unless given with --count
+const DEFAULT_FILMSTRIP_COUNT: usize = 16;
+
+/// Save the frames of the offsets from the initial one in steps of '--step' bytes as PNG images,
+/// numbered in the order of the offsets
+pub fn save_filmstrip(options: CliOptions) -> Result<()> {
+    let count = options.count.unwrap_or(DEFAULT_FILMSTRIP_COUNT);
+    let output_dir = Path::new(options.output_dir.as_deref().unwrap_or(".")).to_owned();
+    let stem = Path::new(&options.filename)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "binocle".into());
+    let step = options.step;
+    let mut binocle = Binocle::new(options, false)?;
+    std::fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Could not create '{}'", output_dir.display()))?;
+
+    let settings = &binocle.settings;
+    let rows = HEIGHT as i64 / settings.zoom_factor() as i64;
+    let step = step.unwrap_or(rows * settings.row_length()).max(1);
+    let start = settings.view_start();
+
+    for i in 0..count {
+        let offset = start + i as i64 * step;
+        if offset >= binocle.settings.buffer_length {
+            eprintln!(
+                "Reached the end of the file after {} of {} images",
+                i, count
+            );
+            break;
+        }
+        binocle.settings.offset = offset;
+        binocle.settings.offset_fine = 0;
+        binocle.settings.clamp();
+
+        let path = output_dir.join(format!("{}-{:04}.png", stem, i));
+        binocle.render(WIDTH, HEIGHT, 1).save_png(&path)?;
+        eprintln!(
+            "[{}/{}] Saved {} (offset 0x{:x})",
+            i + 1,
+            count,
+            path.display(),
+            offset
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Interpretation of the first selected bytes as the common data types, like the inspector of a
+//! hex editor
+
+use std::convert::TryInto;
+
+/// Number of bytes which are decoded as text
+pub const TEXT_LENGTH: usize = 32;
+
+/// Shown for the data types which need more bytes than are selected
+const NOT_AVAILABLE: &str = "n/a";
+
+/// The value of a data type in both byte orders (which are the same for single bytes and UTF-8)
+pub struct Interpretation {
+    pub name: &'static str,
+    pub little: String,
+    pub big: String,
+}
+
+/// Interpret the start of 'bytes' as each of the integer, float and text types
+pub fn interpretations(bytes: &[u8]) -> Vec<Interpretation> {
+    let text = &bytes[..bytes.len().min(TEXT_LENGTH)];
+    let ascii: String = text
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '·'
+            }
+        })
+        .collect();
+    let utf8 = printable(String::from_utf8_lossy(text).chars());
+
+    vec![
+        number("u8", bytes, u8::from_le_bytes, u8::from_be_bytes),
+        number("i8", bytes, i8::from_le_bytes, i8::from_be_bytes),
+        number("u16", bytes, u16::from_le_bytes, u16::from_be_bytes),
+        number("i16", bytes, i16::from_le_bytes, i16::from_be_bytes),
+        number("u32", bytes, u32::from_le_bytes, u32::from_be_bytes),
+        number("i32", bytes, i32::from_le_bytes, i32::from_be_bytes),
+        number("u64", bytes, u64::from_le_bytes, u64::from_be_bytes),
+        number("i64", bytes, i64::from_le_bytes, i64::from_be_bytes),
+        number("f32", bytes, f32::from_le_bytes, f32::from_be_bytes),
+        number("f64", bytes, f64::from_le_bytes, f64::from_be_bytes),
+        Interpretation {
+            name: "ASCII",
+            little: ascii.clone(),
+            big: ascii,
+        },
+        Interpretation {
+            name: "UTF-8",
+            little: utf8.clone(),
+            big: utf8,
+        },
+        Interpretation {
+            name: "UTF-16",
+            little: utf16(text, u16::from_le_bytes),
+            big: utf16(text, u16::from_be_bytes),
+        },
+    ]
+}
+
+fn number<const N: usize, T: ToString>(
+    name: &'static str,
+    bytes: &[u8],
+    from_le_bytes: fn([u8; N]) -> T,
+    from_be_bytes: fn([u8; N]) -> T,
+) -> Interpretation {
+    let value = |from_bytes: fn([u8; N]) -> T| match bytes.get(..N) {
+        Some(slice) => from_bytes(slice.try_into().unwrap()).to_string(),
+        None => NOT_AVAILABLE.into(),
+    };
+    Interpretation {
+        name,
+        little: value(from_le_bytes),
+        big: value(from_be_bytes),
+    }
+}
+
+fn utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    if bytes.len() < 2 {
+        return NOT_AVAILABLE.into();
+    }
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes(pair.try_into().unwrap()));
+    printable(char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)))
+}
+
+/// Replace the control characters, which would break the layout of the panel
+fn printable(chars: impl Iterator<Item = char>) -> String {
+    chars
+        .map(|c| if c.is_control() { '·' } else { c })
+        .collect()
+}
+
+#[test]
+fn interpretations_of_short_selection() {
+    let rows = interpretations(&[0x01, 0x02, b'A']);
+    let row = |name| rows.iter().find(|row| row.name == name).unwrap();
+    assert_eq!(row("u8").little, "1");
+    assert_eq!(row("u16").little, "513");
+    assert_eq!(row("u16").big, "258");
+    assert_eq!(row("u32").little, "n/a");
+    assert_eq!(row("f64").big, "n/a");
+    assert_eq!(row("ASCII").little, "··A");
+    assert_eq!(row("UTF-16").big, "\u{102}");
+}
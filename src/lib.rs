@@ -0,0 +1,31 @@
+//! The library part of binocle, which allows rendering views of binary data without a window
+//! (e.g. in integration tests)
+
+mod action_log;
+mod alignment;
+mod analysis;
+mod annotation;
+pub mod binocle;
+pub mod buffer;
+mod clipboard;
+mod config;
+pub mod crash;
+mod datatype;
+pub mod download;
+mod editor;
+pub mod event_loop;
+mod export;
+mod expression;
+mod font;
+mod gui;
+pub mod headless;
+mod inspector;
+mod loader;
+pub mod options;
+mod region;
+mod scaling;
+mod search;
+pub mod settings;
+mod spectrum;
+mod style;
+mod view;
@@ -0,0 +1,86 @@
+//! Reading of large files (or downloads) on a background thread, to keep the GUI responsive
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Size of the chunks in which the file is read, which is also the granularity of the progress
+const CHUNK_SIZE: usize = 4 << 20;
+
+pub struct Loader {
+    bytes_read: Arc<AtomicU64>,
+    handle: Option<JoinHandle<io::Result<Vec<u8>>>>,
+}
+
+impl Loader {
+    /// Start reading the whole file
+    pub fn start(path: PathBuf) -> Self {
+        Self::spawn(move |progress| {
+            let mut file = File::open(path)?;
+            let capacity = file.metadata()?.len() as usize;
+            read_all(&mut file, capacity, &progress)
+        })
+    }
+
+    /// Start reading everything from the reader, e.g. a download, of which 'size' bytes are
+    /// expected
+    pub fn from_reader(mut reader: Box<dyn Read + Send>, size: u64) -> Self {
+        Self::spawn(move |progress| read_all(&mut reader, size as usize, &progress))
+    }
+
+    fn spawn<F>(read: F) -> Self
+    where
+        F: FnOnce(Arc<AtomicU64>) -> io::Result<Vec<u8>> + Send + 'static,
+    {
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        let progress = bytes_read.clone();
+        let handle = std::thread::spawn(move || read(progress));
+
+        Self {
+            bytes_read,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// The contents of the file once the thread is done, without blocking
+    pub fn try_finish(&mut self) -> Option<io::Result<Vec<u8>>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        let result = self.handle.take()?.join().unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The loading thread panicked",
+            ))
+        });
+        Some(result)
+    }
+}
+
+/// Read until the end in chunks, storing the number of bytes read so far in 'progress'
+fn read_all<R: Read + ?Sized>(
+    reader: &mut R,
+    capacity: usize,
+    progress: &AtomicU64,
+) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(capacity);
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => return Ok(data),
+            Ok(len) => {
+                data.extend_from_slice(&chunk[..len]);
+                progress.store(data.len() as u64, Ordering::Relaxed);
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -1,17 +1,31 @@
-use crate::options::CliOptions;
+use binocle::{buffer, crash, download, event_loop, headless, options::CliOptions};
 use clap::Parser;
 
-mod binocle;
-mod buffer;
-mod datatype;
-mod event_loop;
-mod gui;
-mod options;
-mod settings;
-mod style;
-mod view;
-
 fn main() -> anyhow::Result<()> {
-    let options = CliOptions::parse();
-    event_loop::run(options)
+    env_logger::init();
+
+    let mut options = CliOptions::parse();
+    let resolve = |path: &str| -> anyhow::Result<String> {
+        if download::is_url(path) {
+            return Ok(path.into());
+        }
+        Ok(buffer::resolve_path(path)?.to_string_lossy().into_owned())
+    };
+    options.filename = resolve(&options.filename)?;
+    options.other_files = options
+        .other_files
+        .iter()
+        .map(|path| resolve(path))
+        .collect::<anyhow::Result<_>>()?;
+    crash::install_panic_hook(&options.filename);
+
+    if options.render_hash {
+        headless::print_render_hash(options)
+    } else if options.analyze {
+        headless::print_analysis(options)
+    } else if options.filmstrip {
+        headless::save_filmstrip(options)
+    } else {
+        event_loop::run(options)
+    }
 }
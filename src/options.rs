@@ -1,13 +1,101 @@
 use clap::{ArgEnum, Parser};
 
+use crate::expression;
+use crate::settings::{PixelStyle, MIN_WINDOW_HEIGHT, MIN_WINDOW_WIDTH};
+
 #[derive(Parser)]
 #[clap(version, about)]
 pub struct CliOptions {
     pub filename: String,
 
+    /// Other versions of the file, to switch between with V while keeping the view
+    #[clap(value_name = "OTHER_VERSIONS")]
+    pub other_files: Vec<String>,
+
     /// Whether to use memory mapping to read the file contents or not
     #[clap(long, arg_enum, default_value = "mmap")]
     pub backing: BackingOption,
+
+    /// Initial width of the grid
+    #[clap(long)]
+    pub width: Option<isize>,
+
+    /// Initial offset into the file
+    #[clap(long)]
+    pub offset: Option<i64>,
+
+    /// Initial stride
+    #[clap(long)]
+    pub stride: Option<isize>,
+
+    /// Initial zoom level
+    #[clap(long)]
+    pub zoom: Option<isize>,
+
+    /// Initial pixel style
+    #[clap(long, arg_enum)]
+    pub style: Option<PixelStyle>,
+
+    /// Base address to show virtual addresses (base + offset) instead of file offsets, like
+    /// '0x400000'
+    #[clap(long, value_name = "ADDRESS", parse(try_from_str = expression::evaluate))]
+    pub base_address: Option<i64>,
+
+    /// Initial width of the window, instead of the one of the last session
+    #[clap(long, value_name = "PIXELS", parse(try_from_str = window_width))]
+    pub width_px: Option<u32>,
+
+    /// Initial height of the window, instead of the one of the last session
+    #[clap(long, value_name = "PIXELS", parse(try_from_str = window_height))]
+    pub height_px: Option<u32>,
+
+    /// Allow opening block and character devices like '/dev/sdb'
+    #[clap(long)]
+    pub allow_devices: bool,
+
+    /// Record every change of the view with timestamps to the given file
+    #[clap(long, value_name = "FILE")]
+    pub record: Option<String>,
+
+    /// Play back the changes of the view recorded with --record
+    #[clap(long, value_name = "FILE", conflicts_with = "record")]
+    pub replay: Option<String>,
+
+    /// Render in software (with OpenGL), for machines without a usable GPU. This is also done
+    /// automatically if the GPU can not be initialized. The backend can be chosen with the
+    /// WGPU_BACKEND environment variable as well.
+    #[clap(long)]
+    pub software: bool,
+
+    /// Render a single frame without opening a window and print a hash of its pixels
+    #[clap(long)]
+    pub render_hash: bool,
+
+    /// Print a summary of the entropy, the byte histogram, signatures and strings of the file
+    /// without opening a window
+    #[clap(long, conflicts_with = "render-hash")]
+    pub analyze: bool,
+
+    /// Print the summary of --analyze as JSON
+    #[clap(long, requires = "analyze")]
+    pub json: bool,
+
+    /// Save a PNG image for each of a sequence of offsets (a filmstrip of the file), starting at
+    /// the initial offset, without opening a window
+    #[clap(long, conflicts_with_all = &["render-hash", "analyze"])]
+    pub filmstrip: bool,
+
+    /// Distance between the offsets of the filmstrip, one screen by default
+    #[clap(long, value_name = "BYTES", requires = "filmstrip", parse(try_from_str = expression::evaluate))]
+    pub step: Option<i64>,
+
+    /// Number of images of the filmstrip [default: 16]
+    #[clap(long, requires = "filmstrip")]
+    pub count: Option<usize>,
+
+    /// Directory to save the images of the filmstrip in [default: the current directory]
+    #[clap(long, value_name = "DIR", requires = "filmstrip")]
+    pub output_dir: Option<String>,
 }
 
 #[derive(ArgEnum, Copy, Clone)]
@@ -15,3 +103,26 @@ pub enum BackingOption {
     File,
     Mmap,
 }
+
+fn window_size(value: &str, min: u32) -> Result<u32, String> {
+    let size: u32 = value.parse().map_err(|e| format!("{}", e))?;
+    if size < min {
+        return Err(format!("must be at least {} pixels", min));
+    }
+    Ok(size)
+}
+
+fn window_width(value: &str) -> Result<u32, String> {
+    window_size(value, MIN_WINDOW_WIDTH)
+}
+
+fn window_height(value: &str) -> Result<u32, String> {
+    window_size(value, MIN_WINDOW_HEIGHT)
+}
+
+#[test]
+fn window_size_has_a_minimum() {
+    assert_eq!(window_width("1280"), Ok(1280));
+    assert!(window_width("320").is_err());
+    assert!(window_height("tall").is_err());
+}
@@ -0,0 +1,84 @@
+//! Labeled byte ranges, like the header or the code of a file, which are stored in a sidecar file
+//! next to it
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Appended to the file name for the name of its sidecar file
+const SIDECAR_EXTENSION: &str = ".regions.json";
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Region {
+    /// File offset of the first byte
+    pub start: i64,
+    /// File offset after the last byte
+    pub end: i64,
+    pub label: String,
+    pub color: [u8; 3],
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct RegionMap {
+    pub regions: Vec<Region>,
+}
+
+impl RegionMap {
+    pub fn sidecar_path(filename: &str) -> PathBuf {
+        PathBuf::from(format!("{}{}", filename, SIDECAR_EXTENSION))
+    }
+
+    /// Load the map of the file, or an empty one if it has no sidecar file yet
+    pub fn load(filename: &str) -> Result<Self> {
+        let path = Self::sidecar_path(filename);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Could not read '{}'", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Could not parse '{}'", path.display()))
+    }
+
+    pub fn save(&self, filename: &str) -> Result<PathBuf> {
+        let path = Self::sidecar_path(filename);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Could not write '{}'", path.display()))?;
+        Ok(path)
+    }
+
+    /// The region shown at the offset. Of overlapping regions, the shortest one wins, such that
+    /// e.g. a header stays visible within a region spanning the whole file. Of equally long
+    /// ones, the one added last wins.
+    pub fn region_at(&self, offset: i64) -> Option<&Region> {
+        self.regions
+            .iter()
+            .rev()
+            .filter(|region| (region.start..region.end).contains(&offset))
+            .min_by_key(|region| region.end - region.start)
+    }
+}
+
+#[test]
+fn shortest_region_wins() {
+    let region = |start, end, label: &str| Region {
+        start,
+        end,
+        label: label.into(),
+        color: [0, 0, 0],
+    };
+    let map = RegionMap {
+        regions: vec![
+            region(0, 100, "file"),
+            region(10, 20, "header"),
+            region(10, 20, "magic"),
+        ],
+    };
+    let label = |offset| map.region_at(offset).map(|region| region.label.as_str());
+    assert_eq!(label(5), Some("file"));
+    assert_eq!(label(15), Some("magic"));
+    assert_eq!(label(20), Some("file"));
+    assert_eq!(label(100), None);
+}
@@ -0,0 +1,301 @@
+use pixels::wgpu::{self, util::DeviceExt};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScalingFilter {
+    /// Sharp, every byte stays a crisp block of pixels
+    Nearest,
+    /// Smooth interpolation between neighboring pixels
+    Linear,
+}
+
+/// Replacement for the scaling renderer of 'pixels', which always uses nearest neighbor
+/// filtering. It scales the pixel buffer to the surface in the same way, but lets us choose the
+/// texture filter. The pixel buffer is scaled into an area at the top left of the surface, which
+/// leaves room for the side panel.
+pub struct ScalingRenderer {
+    vertex_buffer: wgpu::Buffer,
+    uniform_buffer: wgpu::Buffer,
+    nearest_bind_group: wgpu::BindGroup,
+    linear_bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+    texture_size: (f32, f32),
+    area_size: (u32, u32),
+    clip_rect: (u32, u32, u32, u32),
+}
+
+impl ScalingRenderer {
+    pub fn new(pixels: &pixels::Pixels, texture_size: (u32, u32), area_size: (u32, u32)) -> Self {
+        let device = pixels.device();
+        let texture_view = pixels
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let module = device.create_shader_module(&wgpu::include_wgsl!("shaders/scale.wgsl"));
+
+        let create_sampler = |filter| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("binocle_scaling_renderer_sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: filter,
+                min_filter: filter,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                lod_min_clamp: 0.0,
+                lod_max_clamp: 1.0,
+                compare: None,
+                anisotropy_clamp: None,
+                border_color: None,
+            })
+        };
+        let nearest_sampler = create_sampler(wgpu::FilterMode::Nearest);
+        let linear_sampler = create_sampler(wgpu::FilterMode::Linear);
+
+        // One full-screen triangle of position and texture coordinates
+        let vertex_data: [[[f32; 2]; 2]; 3] = [
+            [[-1.0, -1.0], [0.0, 0.0]],
+            [[3.0, -1.0], [2.0, 0.0]],
+            [[-1.0, 3.0], [0.0, 2.0]],
+        ];
+        let vertex_data_slice = bytemuck::cast_slice(&vertex_data);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("binocle_scaling_renderer_vertex_buffer"),
+            contents: vertex_data_slice,
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let vertex_buffer_layout = wgpu::VertexBufferLayout {
+            array_stride: (vertex_data_slice.len() / vertex_data.len()) as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: 4 * 2,
+                    shader_location: 1,
+                },
+            ],
+        };
+
+        let texture_size = (texture_size.0 as f32, texture_size.1 as f32);
+        let (transform, clip_rect) =
+            scaling_matrix(texture_size, (area_size.0 as f32, area_size.1 as f32));
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("binocle_scaling_renderer_matrix_uniform_buffer"),
+            contents: bytemuck::cast_slice(&transform),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("binocle_scaling_renderer_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        filtering: true,
+                        comparison: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let create_bind_group = |sampler| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("binocle_scaling_renderer_bind_group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let nearest_bind_group = create_bind_group(&nearest_sampler);
+        let linear_bind_group = create_bind_group(&linear_sampler);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("binocle_scaling_renderer_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("binocle_scaling_renderer_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[vertex_buffer_layout],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format: pixels.render_texture_format(),
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent::REPLACE,
+                        alpha: wgpu::BlendComponent::REPLACE,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+        });
+
+        Self {
+            vertex_buffer,
+            uniform_buffer,
+            nearest_bind_group,
+            linear_bind_group,
+            render_pipeline,
+            texture_size,
+            area_size,
+            clip_rect,
+        }
+    }
+
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+        filter: ScalingFilter,
+    ) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("binocle_scaling_renderer_render_pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: render_target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_bind_group(
+            0,
+            match filter {
+                ScalingFilter::Nearest => &self.nearest_bind_group,
+                ScalingFilter::Linear => &self.linear_bind_group,
+            },
+            &[],
+        );
+        rpass.set_viewport(
+            0.0,
+            0.0,
+            self.area_size.0 as f32,
+            self.area_size.1 as f32,
+            0.0,
+            1.0,
+        );
+        rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        rpass.set_scissor_rect(
+            self.clip_rect.0,
+            self.clip_rect.1,
+            self.clip_rect.2,
+            self.clip_rect.3,
+        );
+        rpass.draw(0..3, 0..1);
+    }
+
+    pub fn area_size(&self) -> (u32, u32) {
+        self.area_size
+    }
+
+    pub fn resize(&mut self, queue: &wgpu::Queue, width: u32, height: u32) {
+        let (transform, clip_rect) =
+            scaling_matrix(self.texture_size, (width as f32, height as f32));
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&transform));
+        self.area_size = (width, height);
+        self.clip_rect = clip_rect;
+    }
+
+    /// Position of the pixel of the pixel buffer under a physical position in the window, if any
+    pub fn window_pos_to_pixel(&self, (x, y): (f32, f32)) -> Option<(isize, isize)> {
+        let (texture_width, texture_height) = self.texture_size;
+        let scale = (self.area_size.0 as f32 / texture_width)
+            .min(self.area_size.1 as f32 / texture_height)
+            .max(1.0)
+            .floor();
+        let (left, top, width, height) = self.clip_rect;
+        let (x, y) = (x - left as f32, y - top as f32);
+        let inside = (0.0..width as f32).contains(&x) && (0.0..height as f32).contains(&y);
+        inside.then(|| ((x / scale) as isize, (y / scale) as isize))
+    }
+}
+
+/// Computes the transformation matrix and the clipping rectangle that scale the texture by the
+/// largest integer factor that fits on the screen, the same way 'pixels' does.
+fn scaling_matrix(
+    texture_size: (f32, f32),
+    screen_size: (f32, f32),
+) -> ([f32; 16], (u32, u32, u32, u32)) {
+    let (texture_width, texture_height) = texture_size;
+    let (screen_width, screen_height) = screen_size;
+
+    let scale = (screen_width / texture_width)
+        .min(screen_height / texture_height)
+        .max(1.0)
+        .floor();
+
+    let scaled_width = texture_width * scale;
+    let scaled_height = texture_height * scale;
+
+    let sw = scaled_width / screen_width;
+    let sh = scaled_height / screen_height;
+    let tx = (texture_width / screen_width - 1.0).max(0.0);
+    let ty = (1.0 - texture_height / screen_height).min(0.0);
+    #[rustfmt::skip]
+    let transform: [f32; 16] = [
+        sw,  0.0, 0.0, 0.0,
+        0.0, -sh, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        tx,  ty, 0.0, 1.0,
+    ];
+
+    let clip_rect = {
+        let scaled_width = scaled_width.min(screen_width);
+        let scaled_height = scaled_height.min(screen_height);
+        let x = ((screen_width - scaled_width) / 2.0) as u32;
+        let y = ((screen_height - scaled_height) / 2.0) as u32;
+
+        (x, y, scaled_width as u32, scaled_height as u32)
+    };
+
+    (transform, clip_rect)
+}
@@ -0,0 +1,65 @@
+//! Searching the buffer for byte patterns
+
+/// Parse a search pattern: either hexadecimal bytes like 'de ad be ef' (whitespace is
+/// optional), or a text in double quotes like '"PNG"'.
+pub fn parse_pattern(query: &str) -> Result<Vec<u8>, String> {
+    let query = query.trim();
+    if let Some(text) = query
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+    {
+        return if text.is_empty() {
+            Err("Empty pattern".into())
+        } else {
+            Ok(text.as_bytes().to_vec())
+        };
+    }
+
+    let digits: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() {
+        return Err("Empty pattern".into());
+    }
+    if digits.len() % 2 != 0 {
+        return Err("Odd number of hexadecimal digits".into());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16)
+                .map_err(|_| format!("Invalid hexadecimal byte '{}'", pair))
+        })
+        .collect()
+}
+
+/// Offsets of the first 'max_matches' (possibly overlapping) occurrences of the pattern
+pub fn find_all(data: &[u8], pattern: &[u8], max_matches: usize) -> Vec<i64> {
+    if pattern.is_empty() {
+        return vec![];
+    }
+    data.windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| window == &pattern)
+        .map(|(offset, _)| offset as i64)
+        .take(max_matches)
+        .collect()
+}
+
+#[test]
+fn parse_hex_and_text_patterns() {
+    assert_eq!(
+        parse_pattern("de ad BE EF"),
+        Ok(vec![0xde, 0xad, 0xbe, 0xef])
+    );
+    assert_eq!(parse_pattern("0a0b"), Ok(vec![0x0a, 0x0b]));
+    assert_eq!(parse_pattern("\"PNG\""), Ok(b"PNG".to_vec()));
+    assert!(parse_pattern("abc").is_err());
+    assert!(parse_pattern("zz").is_err());
+    assert!(parse_pattern("").is_err());
+}
+
+#[test]
+fn find_overlapping_matches() {
+    assert_eq!(find_all(&[0, 0, 0, 1, 0, 0], &[0, 0], 10), vec![0, 1, 4]);
+    assert_eq!(find_all(&[0, 0, 0, 1, 0, 0], &[0, 0], 2), vec![0, 1]);
+}
@@ -1,14 +1,34 @@
+use std::ops::Range;
+
+use clap::ArgEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::annotation::Annotation;
 use crate::datatype::{Endianness, Signedness};
+use crate::editor;
+use crate::export::Image;
+use crate::inspector::Interpretation;
+use crate::region::RegionMap;
+use crate::scaling::ScalingFilter;
+use crate::style::{AlphaMode, CategoryRule, Color, Palette};
 
 pub const WIDTH: u32 = 1366;
 pub const HEIGHT: u32 = 1024;
 
-#[derive(PartialEq)]
+/// The window can not be made smaller than this, which leaves room for the panel and the canvas
+pub const MIN_WINDOW_WIDTH: u32 = 640;
+pub const MIN_WINDOW_HEIGHT: u32 = 480;
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum PixelStyle {
     Grayscale,
     Colorful,
     Category,
     Entropy,
+    Delta,
+    Position,
+    RunLength,
+    Relief,
     GradientMagma,
     GradientPlasma,
     GradientViridis,
@@ -20,9 +40,42 @@ pub enum PixelStyle {
     Rgb,
     Bgr,
     Datatype,
+    Pointer,
 }
 
-#[derive(Clone, PartialEq)]
+impl PixelStyle {
+    /// Name of the style, as used on the command line
+    pub fn name(&self) -> &'static str {
+        self.to_possible_value()
+            .map(|value| value.get_name())
+            .unwrap_or("")
+    }
+
+    /// Whether the color of a byte only depends on its value, such that the style is fully
+    /// described by a table of 256 colors
+    pub fn is_single_byte(&self) -> bool {
+        matches!(
+            self,
+            PixelStyle::Grayscale
+                | PixelStyle::Colorful
+                | PixelStyle::Category
+                | PixelStyle::GradientMagma
+                | PixelStyle::GradientPlasma
+                | PixelStyle::GradientViridis
+                | PixelStyle::GradientRainbow
+                | PixelStyle::GradientTurbo
+                | PixelStyle::GradientCubehelix
+        )
+    }
+
+    /// Whether the color of a byte depends on where it lies in all of the data, such that it
+    /// can't be shown for a copy of only some of the data, like the mirrored bytes
+    pub fn is_positional(&self) -> bool {
+        matches!(self, PixelStyle::Position | PixelStyle::RunLength)
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum GuiDatatype {
     Integer8,
     Integer16,
@@ -32,10 +85,615 @@ pub enum GuiDatatype {
     Float64,
 }
 
+impl GuiDatatype {
+    /// Size of a value in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            GuiDatatype::Integer8 => 1,
+            GuiDatatype::Integer16 => 2,
+            GuiDatatype::Integer32 | GuiDatatype::Float32 => 4,
+            GuiDatatype::Integer64 | GuiDatatype::Float64 => 8,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct DatatypeSettings {
     pub datatype: GuiDatatype,
     pub signedness: Signedness,
     pub endianness: Endianness,
+    pub value_range: (f32, f32),
+    /// Use a diverging colormap centered at zero
+    #[serde(default)]
+    pub diverging: bool,
+}
+
+/// Configuration of the individual pixel styles. These are kept when switching between styles
+/// and persisted in the configuration file.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleSettings {
+    pub grayscale_gamma: f32,
+    /// Look up the gradient styles from the other end
+    pub reverse_gradient: bool,
+    pub alpha_mode: AlphaMode,
+    pub palette: Palette,
+    pub category_rules: Vec<CategoryRule>,
+    /// Shade the bytes outside of the ASCII categories by their value instead of one flat color
+    pub category_shade_other: bool,
+    pub datatype: DatatypeSettings,
+    pub pointer: PointerSettings,
+}
+
+impl Default for StyleSettings {
+    fn default() -> Self {
+        Self {
+            grayscale_gamma: 1.0,
+            reverse_gradient: false,
+            alpha_mode: AlphaMode::Opaque,
+            palette: Palette::Standard,
+            category_rules: vec![],
+            category_shade_other: false,
+            datatype: DatatypeSettings {
+                datatype: GuiDatatype::Integer16,
+                signedness: Signedness::Unsigned,
+                endianness: Endianness::Little,
+                value_range: (0.0, 100.0),
+                diverging: false,
+            },
+            pointer: PointerSettings {
+                word_size: 8,
+                endianness: Endianness::Little,
+                base: 0,
+                size: 0,
+            },
+        }
+    }
+}
+
+/// Address space into which words are considered to be pointers
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointerSettings {
+    /// Size of a word in bytes, either 4 or 8
+    pub word_size: usize,
+    pub endianness: Endianness,
+    pub base: u64,
+    /// Size of the address space, zero for the length of the file
+    pub size: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FoldMode {
+    /// Bitwise difference of every copy to the first one. Bytes that are identical in all
+    /// periods become zero.
+    Xor,
+    /// Maximum value across all copies
+    Max,
+}
+
+/// Split every byte into two pixels, one for each nibble
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NibbleMode {
+    Off,
+    HighFirst,
+    LowFirst,
+}
+
+/// How the bytes which the stride steps over are combined into a single pixel
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StrideSampling {
+    /// Only the first byte of every step is shown
+    Skip,
+    /// Average of all bytes of the step
+    Mean,
+    /// Largest byte of the step, such that isolated high values stay visible
+    Max,
+}
+
+/// Folds the data with a given period, such that 'offset', 'offset + period',
+/// 'offset + 2 * period', … all map to the same pixel.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct FoldSettings {
+    pub enabled: bool,
+    pub period: i64,
+    pub copies: i64,
+    pub mode: FoldMode,
+}
+
+/// Shows only one of several interleaved streams of samples (like the channels of audio data):
+/// of every 'streams' consecutive samples of 'sample_size' bytes, only the one with index 'stream'
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterleaveSettings {
+    pub enabled: bool,
+    pub streams: i64,
+    pub sample_size: i64,
+    pub stream: i64,
+}
+
+impl InterleaveSettings {
+    /// Position relative to the start of the view of the byte at 'index' of the selected stream
+    pub fn source_index(&self, index: i64) -> i64 {
+        if !self.enabled {
+            return index;
+        }
+        let sample = index / self.sample_size;
+        (sample * self.streams + self.stream) * self.sample_size + index % self.sample_size
+    }
+}
+
+/// How to open a file which is too large to be read without asking
+#[derive(Clone, Copy, PartialEq)]
+pub enum LoadChoice {
+    Read,
+    Mmap,
+    /// Don't download the file, and keep the empty data
+    Cancel,
+}
+
+/// State of a large file which is not opened yet, or read in the background
+pub struct LoadingState {
+    /// Zero if the size of a download is not known
+    pub file_size: u64,
+    pub bytes_read: u64,
+    pub started: bool,
+    pub choice: Option<LoadChoice>,
+    /// The file is downloaded from a URL, which can not be mapped into memory
+    pub download: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EntropyJump {
+    Highest,
+    Lowest,
+}
+
+/// Only show some of the bits of every byte
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct BitMaskSettings {
+    pub enabled: bool,
+    pub mask: u8,
+    pub shift: u8,
+}
+
+impl BitMaskSettings {
+    /// Maps every byte to its masked and shifted bits, stretched to the full range of byte
+    /// values. A single bit thus becomes either 0 or 255.
+    pub fn byte_map(&self) -> [u8; 256] {
+        let shift = self.shift.min(7);
+        let max = (self.mask >> shift).max(1) as u32;
+        let mut byte_map = [0; 256];
+        for (byte, mapped) in byte_map.iter_mut().enumerate() {
+            let bits = ((byte as u8 & self.mask) >> shift) as u32;
+            *mapped = (bits.min(max) * 255 / max) as u8;
+        }
+        byte_map
+    }
+}
+
+/// Stretch the range of byte values in the view to the full range, for low contrast regions
+#[derive(Clone, PartialEq)]
+pub struct ContrastSettings {
+    pub enabled: bool,
+    /// Keep the range instead of following the view, to compare regions with the same stretch
+    pub locked: bool,
+    /// Lowest and highest byte value in the view (after the bit mask)
+    pub min: u8,
+    pub max: u8,
+}
+
+impl Default for ContrastSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            locked: false,
+            min: 0,
+            max: 255,
+        }
+    }
+}
+
+impl ContrastSettings {
+    /// Maps the range from 'min' to 'max' linearly to 0 to 255, and clamps the bytes outside
+    /// of it (which only occur while the range is locked)
+    pub fn byte_map(&self) -> [u8; 256] {
+        let (min, max) = (self.min as u32, self.max as u32);
+        let mut byte_map = [0; 256];
+        for (byte, mapped) in byte_map.iter_mut().enumerate() {
+            let byte = (byte as u32).clamp(min, max.max(min));
+            *mapped = ((byte - min) * 255 / max.saturating_sub(min).max(1)) as u8;
+        }
+        byte_map
+    }
+}
+
+/// Structure of the data as a sequence of fixed-size records
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordSettings {
+    pub enabled: bool,
+    /// Size of a record in bytes
+    pub size: isize,
+    /// Index of the byte within the record to isolate
+    pub field: isize,
+}
+
+/// The part of the layout which is changed when fitting the whole file on the canvas
+#[derive(Clone, Copy)]
+pub struct SavedLayout {
+    pub offset: i64,
+    pub offset_fine: i64,
+    pub width: isize,
+    pub stride: isize,
+    pub zoom: isize,
+}
+
+/// The view likely shows image data if its rows correlate at least this much
+pub const LIKELY_IMAGE_CORRELATION: f64 = 0.75;
+
+/// Range of bytes selected by dragging over the canvas. It is stored as file offsets, such that
+/// it stays on the same bytes when the view moves.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Selection {
+    /// Offset of the byte where the drag started
+    pub anchor: i64,
+    /// Offset of the byte where the drag is now or ended
+    pub end: i64,
+}
+
+impl Selection {
+    /// The selected offsets, in either direction of the drag
+    pub fn range(&self) -> Range<i64> {
+        self.anchor.min(self.end)..self.anchor.max(self.end) + 1
+    }
+}
+
+/// A row of the canvas, as it was rendered when it was pinned, which is kept at the top edge of
+/// the canvas to compare other rows against it
+pub struct PinnedRow {
+    /// File offset of the first byte of the row
+    pub offset: i64,
+    pub width: isize,
+    pub height: isize,
+    /// RGBA pixels of the row
+    pub pixels: Vec<u8>,
+}
+
+/// The layout and style of the view once a file was opened, with the configuration and the
+/// options applied, which resetting the view goes back to
+pub struct StartupSettings {
+    pub width: isize,
+    pub offset: i64,
+    pub stride: isize,
+    pub zoom: isize,
+    pub pixel_style: PixelStyle,
+    pub style_settings: StyleSettings,
+}
+
+/// Plots the value of each byte against its offset, like an oscilloscope trace
+pub struct ProfileSettings {
+    pub enabled: bool,
+    /// Interpret the bytes as two's complement, with zero in the middle of the canvas
+    pub signed: bool,
+}
+
+/// Shows the same view in several styles next to each other
+pub struct CompareSettings {
+    pub enabled: bool,
+    /// Number of tiles, between two and four
+    pub count: usize,
+    pub styles: [PixelStyle; 4],
+}
+
+impl CompareSettings {
+    pub fn styles(&self) -> &[PixelStyle] {
+        &self.styles[..self.count.clamp(2, 4)]
+    }
+
+    /// Number of columns and rows of tiles
+    pub fn layout(&self) -> (isize, isize) {
+        if !self.enabled {
+            (1, 1)
+        } else if self.styles().len() == 2 {
+            (2, 1)
+        } else {
+            (2, 2)
+        }
+    }
+}
+
+/// Position of a canvas pixel in the grid
+#[derive(Clone, Copy, PartialEq)]
+pub struct GridPosition {
+    /// Index of the pane (only non-zero for the right pane in split view)
+    pub pane: usize,
+    pub column: isize,
+    pub row: isize,
+}
+
+impl GridPosition {
+    pub fn view_index(&self, width: isize) -> isize {
+        self.row * width + self.column
+    }
+}
+
+/// Per-column statistics over all visible rows, to help with guessing field boundaries
+#[derive(Default)]
+pub struct ColumnProfile {
+    pub visible: bool,
+    /// Standard deviation of the byte values in each column
+    pub deviation: Vec<f32>,
+    pub selected_column: Option<isize>,
+    /// Values of the selected column in each of the visible rows
+    pub selected_values: Vec<u8>,
+}
+
+/// Versions of the file given on the command line, which are shown with the same view
+#[derive(Default)]
+pub struct VersionSettings {
+    pub count: usize,
+    /// Index and file name of the version which is shown
+    pub current: usize,
+    pub name: String,
+    /// Go this many versions forward (or backward), wrapping around
+    pub step_requested: Option<isize>,
+    /// Offset in the original file of a version which was detached from a selection
+    pub detached_from: Option<i64>,
+    /// Add the selected bytes as a version of their own, starting at offset 0
+    pub detach_requested: bool,
+    /// The shown data is read from a file, which reloading and the editor need (unlike e.g. the
+    /// data from the clipboard)
+    pub file_backed: bool,
+}
+
+/// Hides a trailing run of padding bytes (like erased flash), by shortening the buffer length
+/// which the view is laid out for
+pub struct PaddingSettings {
+    pub enabled: bool,
+    pub byte: u8,
+    /// Number of hidden bytes at the end of the file
+    pub trimmed: i64,
+    /// Whether trimming was enabled and the padding byte, when the length was last computed
+    pub source: Option<(bool, u8)>,
+}
+
+impl Default for PaddingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            byte: 0xff,
+            trimmed: 0,
+            source: None,
+        }
+    }
+}
+
+/// Colors of all byte values in the current style, for the color scale in the side panel. Only
+/// the styles which color every byte by its value have one.
+#[derive(Default)]
+pub struct ColorScale {
+    pub colors: Vec<Color>,
+    /// Style and style settings the colors were computed for
+    pub source: Option<(PixelStyle, StyleSettings)>,
+}
+
+/// The first bytes of the selection interpreted as integers, floats and text
+#[derive(Default)]
+pub struct Inspector {
+    pub visible: bool,
+    /// Empty without a selection
+    pub interpretations: Vec<Interpretation>,
+}
+
+/// Labeled ranges of the file, which are tinted on the canvas
+pub struct Regions {
+    pub visible: bool,
+    pub map: RegionMap,
+    /// Label and color of the next region added from the selection
+    pub label: String,
+    pub color: [u8; 3],
+    pub add_requested: bool,
+    pub save_requested: bool,
+    /// The map changed since it was loaded or saved
+    pub modified: bool,
+}
+
+impl Default for Regions {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            map: RegionMap::default(),
+            label: "header".into(),
+            color: [255, 0, 128],
+            add_requested: false,
+            save_requested: false,
+            modified: false,
+        }
+    }
+}
+
+/// Entropy of the whole file as a strip below the canvas, with the view marked on it
+#[derive(Default)]
+pub struct EntropyTimeline {
+    pub visible: bool,
+    /// Normalized entropies of equally sized segments of the file, computed once per file
+    pub entropies: Vec<f32>,
+}
+
+/// Blends the next version of the file over the shown one, to line up related files
+pub struct OverlaySettings {
+    pub enabled: bool,
+    /// Opacity of the overlaid version, between 0 and 1
+    pub alpha: f32,
+    /// Offset of the overlaid version relative to the shown one, in bytes
+    pub delta: i64,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            alpha: 0.5,
+            delta: 0,
+        }
+    }
+}
+
+/// Blends the data mirrored around an axis over the shown one, such that palindromic regions
+/// centered on the axis line up with themselves
+pub struct MirrorSettings {
+    pub enabled: bool,
+    /// Opacity of the mirrored data, between 0 and 1
+    pub alpha: f32,
+    /// File offset of the mirror axis, rounded to half bytes such that it can lie between two bytes
+    pub axis: f64,
+}
+
+impl Default for MirrorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            alpha: 0.5,
+            axis: 0.0,
+        }
+    }
+}
+
+impl MirrorSettings {
+    /// Sum of every offset and the offset of its mirror image
+    pub fn offset_sum(&self) -> i64 {
+        (2.0 * self.axis).round() as i64
+    }
+}
+
+/// Values of every byte of the records across the visible records, to spot counters and the like
+#[derive(Default)]
+pub struct Sparklines {
+    pub visible: bool,
+    /// For each byte of a record, its value in each visible record
+    pub values: Vec<Vec<u8>>,
+}
+
+/// Values of the bytes in the row under the mouse cursor, to see where a field boundary or a
+/// transition lies within the row. The last row stays when the cursor leaves the canvas.
+#[derive(Default)]
+pub struct RowGraph {
+    pub visible: bool,
+    /// File offset of the first byte of the row
+    pub offset: i64,
+    pub values: Vec<u8>,
+    /// Index of the byte under the cursor in the row
+    pub cursor_column: usize,
+}
+
+/// Spectrum of the visible bytes, to find the period of repeating structures
+#[derive(Default)]
+pub struct SpectrumSettings {
+    pub visible: bool,
+    /// Magnitudes of the frequencies 1 to n/2, in cycles per 'len' bytes
+    pub magnitudes: Vec<f32>,
+    pub len: usize,
+    /// Period of the strongest frequency, in bytes (or records, if the stride is not one)
+    pub period: Option<f64>,
+    /// Start, stride and length of the bytes the spectrum was computed from
+    pub source: Option<(i64, isize, usize)>,
+}
+
+/// Candidate widths ranked by how coherent the columns of the visible bytes become, to find the
+/// size of records
+pub struct WidthScores {
+    pub visible: bool,
+    /// Range of the candidate widths, in bytes
+    pub min_width: isize,
+    pub max_width: isize,
+    /// The best widths with the fraction of bytes which equal the byte above them
+    pub candidates: Vec<(isize, f64)>,
+    /// Start, stride and length of the bytes and the range of widths the scores were computed for
+    pub source: Option<(i64, isize, usize, isize, isize)>,
+}
+
+impl Default for WidthScores {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            min_width: 2,
+            max_width: 512,
+            candidates: vec![],
+            source: None,
+        }
+    }
+}
+
+/// Labels for known structures (like file signatures and strings) on the canvas
+#[derive(Default)]
+pub struct AnnotationSettings {
+    pub signatures: bool,
+    pub strings: bool,
+    pub annotations: Vec<Annotation>,
+    /// Visible range of the file and enabled categories the annotations were found for
+    pub source: Option<(i64, i64, bool, bool)>,
+}
+
+/// State of the byte pattern search
+#[derive(Default)]
+pub struct SearchSettings {
+    pub query: String,
+    pub search_requested: bool,
+    pub next_requested: bool,
+    pub pattern_length: i64,
+    /// Sorted offsets of all matches, up to a maximum count
+    pub matches: Vec<i64>,
+    /// Index of the match which was jumped to last
+    pub current: Option<usize>,
+    pub status: String,
+}
+
+impl SearchSettings {
+    /// Index of the match covering the given offset, if any
+    pub fn match_at(&self, offset: i64) -> Option<usize> {
+        let index = self.matches.partition_point(|&start| start <= offset);
+        index
+            .checked_sub(1)
+            .filter(|&i| offset < self.matches[i] + self.pattern_length)
+    }
+}
+
+pub struct ExportSettings {
+    pub dialog_visible: bool,
+    pub path: String,
+
+    pub width: u32,
+    pub height: u32,
+    /// Number of output pixels per on-screen pixel
+    pub scale: u32,
+    /// Render a footer with the file name, offset range, style and palette below the image
+    pub caption: bool,
+
+    pub preview_requested: bool,
+    pub save_requested: bool,
+    pub preview: Option<Image>,
+    pub status: String,
+
+    /// Palette file (.gpl or .csv) for the colors of all byte values
+    pub palette_path: String,
+    pub palette_requested: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            dialog_visible: false,
+            path: "binocle.png".into(),
+            width: WIDTH,
+            height: HEIGHT,
+            scale: 1,
+            caption: false,
+            preview_requested: false,
+            save_requested: false,
+            preview: None,
+            status: "".into(),
+            palette_path: "binocle.gpl".into(),
+            palette_requested: false,
+        }
+    }
 }
 
 pub struct Settings {
@@ -43,35 +701,613 @@ pub struct Settings {
     pub zoom_range: (isize, isize),
 
     pub width: isize,
-    pub offset: isize,
-    pub offset_fine: isize,
+    /// File offsets are always 64 bit wide, independent of the pointer width of the platform
+    pub offset: i64,
+    pub offset_fine: i64,
 
     pub stride: isize,
     pub max_stride: isize,
+    pub stride_sampling: StrideSampling,
+    /// Hatch the canvas while the stride skips bytes, as a reminder that not all are shown
+    pub stride_hatch: bool,
+
+    pub split_view: bool,
+    /// Offset of the right pane relative to the left pane in split view
+    pub split_delta: i64,
+
+    pub fold: FoldSettings,
+    pub interleave: InterleaveSettings,
+    /// Traverse the file from its end to its start, with the offset of the view counted from the
+    /// end
+    pub reverse: bool,
+    pub compare: CompareSettings,
+    pub record: RecordSettings,
+    pub profile: ProfileSettings,
+    pub nibble_mode: NibbleMode,
+    pub bit_mask: BitMaskSettings,
+    pub contrast: ContrastSettings,
+
+    pub scaling_filter: ScalingFilter,
 
     pub pixel_style: PixelStyle,
-    pub datatype_settings: DatatypeSettings,
+    /// The style before the last change of the style, which 'S' switches back to
+    pub previous_pixel_style: Option<PixelStyle>,
+    /// The style as of the last frame, to notice changes wherever they come from
+    pub tracked_pixel_style: Option<PixelStyle>,
+    pub style_settings: StyleSettings,
+    pub color_scale: ColorScale,
+    pub inspector: Inspector,
+    pub regions: Regions,
 
-    pub buffer_length: isize,
+    pub buffer_length: i64,
+    /// Number of bytes in holes of a sparse file
+    pub hole_length: i64,
+    /// Draw the holes of a sparse file like the area outside of the data, instead of as zeros
+    pub show_holes: bool,
+    pub padding: PaddingSettings,
+    /// Repeat the data before its start and past its end, instead of leaving that area empty
+    pub tile_data: bool,
     pub canvas_width: isize,
+    pub canvas_height: isize,
+    /// Upper limit for both dimensions of the canvas, independent of the window size
+    pub max_render_size: u32,
 
-    pub value_range: (f32, f32),
+    /// Position of the mouse cursor on the canvas, if it is not over the GUI
+    pub cursor: Option<(isize, isize)>,
+    pub crosshair_visible: bool,
+    /// Tint printable ASCII bytes green on top of the pixel style
+    pub text_overlay: bool,
 
     pub hex_view_visible: bool,
+    /// Show the bytes after the transforms (like folding) in the hex view instead of the raw ones
+    pub hex_view_transformed: bool,
     pub hex_view: String,
     pub hex_ascii: String,
 
+    pub column_profile: ColumnProfile,
+    pub sparklines: Sparklines,
+    pub row_graph: RowGraph,
+    pub versions: VersionSettings,
+    pub overlay: OverlaySettings,
+    pub mirror: MirrorSettings,
+    pub spectrum: SpectrumSettings,
+    pub entropy_timeline: EntropyTimeline,
+    pub width_scores: WidthScores,
+    /// Correlation of the visible rows with the rows below them, to detect image data
+    pub row_correlation: Option<f64>,
+    pub annotations: AnnotationSettings,
+
+    pub export: ExportSettings,
+    pub search: SearchSettings,
+
+    /// All panels and windows of the GUI can be hidden for an unobstructed view
+    pub gui_visible: bool,
+    /// Window with the list of key bindings
+    pub help_visible: bool,
+    /// Factor for the size of the GUI, on top of the scale factor of the window
+    pub gui_scale: f32,
+    /// Width of the side panel in points, changed by dragging its edge
+    pub panel_width: f32,
+    /// Virtual address of the start of the file, and whether offsets are shown as such addresses
+    pub address_base: i64,
+    pub show_addresses: bool,
+    /// Layout before fitting the whole file on the canvas, restored by fitting again
+    pub fit_restore: Option<SavedLayout>,
+    /// Set until a large file is opened
+    pub loading: Option<LoadingState>,
+    /// Message about the outcome of the last command
+    pub status: String,
+    pub entropy_jump_requested: Option<EntropyJump>,
+    /// Fraction of the file covered by a running pass over the whole file, like the one for
+    /// the entropy jump
+    pub analysis_progress: Option<f32>,
+    /// Command to open an external hex editor, with '{file}' and '{offset}' placeholders
+    pub editor_command: String,
+    pub editor_requested: bool,
+    /// Read the file again, e.g. after it was changed by another program
+    pub reload_requested: bool,
+    /// Files dropped on the window are shown with the current layout, instead of the one they had
+    /// when they were last opened
+    pub keep_layout: bool,
+    /// Replace the data with the bytes from the clipboard
+    pub clipboard_requested: bool,
+    /// Copy the view to the clipboard as an image
+    pub copy_image_requested: bool,
+    pub selection: Option<Selection>,
+    /// Reference row at the top edge of the canvas, which stays when scrolling or resetting
+    pub pinned_row: Option<PinnedRow>,
+    /// Pin the row under the cursor, or the first row of the view without a cursor
+    pub pin_row_requested: bool,
+    /// What resetting the view goes back to, instead of the defaults
+    pub startup: Option<Box<StartupSettings>>,
     pub gui_wants_keyboard: bool,
     pub gui_wants_mouse: bool,
 }
 
 impl Settings {
+    /// Default settings for a buffer of the given length
+    pub fn new(buffer_length: i64) -> Self {
+        Self {
+            buffer_length,
+            split_delta: buffer_length / 2,
+            ..Default::default()
+        }
+    }
+
+    /// Keep the current layout and style as the ones to go back to when resetting the view
+    pub fn keep_as_startup(&mut self) {
+        self.startup = Some(Box::new(StartupSettings {
+            width: self.width,
+            offset: self.offset,
+            stride: self.stride,
+            zoom: self.zoom,
+            pixel_style: self.pixel_style,
+            style_settings: self.style_settings.clone(),
+        }));
+    }
+
+    /// Go back to the settings used at startup, but keep the state which is not related to the
+    /// view (like the export dialog, the search or the size of the canvas).
+    pub fn reset(&mut self) {
+        let export = std::mem::take(&mut self.export);
+        let search = std::mem::take(&mut self.search);
+        let versions = std::mem::take(&mut self.versions);
+        let regions = std::mem::take(&mut self.regions);
+        *self = Self {
+            export,
+            search,
+            versions,
+            regions,
+            // A file which is still being opened has to finish, see 'Binocle::update_loading'
+            loading: self.loading.take(),
+            analysis_progress: self.analysis_progress,
+            canvas_width: self.canvas_width,
+            canvas_height: self.canvas_height,
+            max_render_size: self.max_render_size,
+            gui_visible: self.gui_visible,
+            gui_scale: self.gui_scale,
+            panel_width: self.panel_width,
+            hole_length: self.hole_length,
+            address_base: self.address_base,
+            show_addresses: self.show_addresses,
+            editor_command: std::mem::take(&mut self.editor_command),
+            pinned_row: self.pinned_row.take(),
+            startup: self.startup.take(),
+            ..Self::new(self.buffer_length)
+        };
+        if let Some(startup) = &self.startup {
+            self.width = startup.width;
+            self.offset = startup.offset;
+            self.stride = startup.stride;
+            self.zoom = startup.zoom;
+            self.pixel_style = startup.pixel_style;
+            self.style_settings = startup.style_settings.clone();
+            self.clamp();
+        }
+    }
+
     pub fn zoom_factor(&self) -> isize {
         2isize.pow((self.zoom - 1) as u32)
     }
 
-    pub fn max_offset_fine(&self) -> isize {
-        3 * self.width * self.stride
+    /// Number of grid cells (pixels at zoom level 1) per byte
+    pub fn pixels_per_byte(&self) -> isize {
+        match self.nibble_mode {
+            NibbleMode::Off => 1,
+            NibbleMode::HighFirst | NibbleMode::LowFirst => 2,
+        }
+    }
+
+    /// Number of consecutive bytes the pixel style reads for a grid cell, like the three of a
+    /// color in the RGB style
+    pub fn bytes_per_cell(&self) -> isize {
+        let style_settings = &self.style_settings;
+        match self.pixel_style {
+            PixelStyle::Rgb | PixelStyle::Bgr => 3,
+            PixelStyle::Rgba | PixelStyle::Abgr => 4,
+            PixelStyle::Datatype => style_settings.datatype.datatype.size() as isize,
+            PixelStyle::Pointer => style_settings.pointer.word_size.clamp(1, 8) as isize,
+            _ => 1,
+        }
+    }
+
+    /// Whether the view leaves out bytes, because the stride steps over more bytes than a grid
+    /// cell shows
+    pub fn skips_bytes(&self) -> bool {
+        self.stride > self.bytes_per_cell() && self.stride_sampling == StrideSampling::Skip
+    }
+
+    /// Number of bytes covered by a single row of the grid
+    pub fn row_length(&self) -> i64 {
+        (self.width / self.pixels_per_byte() * self.stride) as i64
+    }
+
+    /// File offset of the first byte in the view
+    pub fn view_start(&self) -> i64 {
+        self.offset + self.offset_fine
+    }
+
+    /// Maps a pixel on a canvas of the given width to its position in the grid
+    pub fn grid_position(
+        &self,
+        (canvas_x, canvas_y): (isize, isize),
+        canvas_width: isize,
+        pixel_size: isize,
+    ) -> GridPosition {
+        let pane_width = if self.split_view {
+            canvas_width / 2
+        } else {
+            canvas_width
+        };
+        let pane = (canvas_x / pane_width).min(1);
+
+        GridPosition {
+            pane: pane as usize,
+            column: (canvas_x - pane * pane_width) / pixel_size,
+            row: canvas_y / pixel_size,
+        }
+    }
+
+    /// Position on the canvas of the grid cell showing the byte at 'offset' (or the last one
+    /// before it, if the stride skips it) in the left pane
+    pub fn canvas_position(&self, offset: i64) -> Option<(isize, isize)> {
+        let index = offset - self.view_start();
+        if index < 0 {
+            return None;
+        }
+        let cell = (index / self.stride as i64) as isize * self.pixels_per_byte();
+        let (row, column) = (cell / self.width, cell % self.width);
+        let zoom_factor = self.zoom_factor();
+        (row < self.visible_rows()).then_some((column * zoom_factor, row * zoom_factor))
+    }
+
+    /// Size of a single tile of the canvas when comparing styles, or of the whole canvas
+    pub fn tile_size(&self) -> (isize, isize) {
+        let (columns, rows) = self.compare.layout();
+        (self.canvas_width / columns, self.canvas_height / rows)
+    }
+
+    /// Position of the mouse cursor in the grid of the on-screen canvas
+    pub fn cursor_grid_position(&self) -> Option<GridPosition> {
+        let (cursor_x, cursor_y) = self.cursor?;
+        let (tile_width, tile_height) = self.tile_size();
+        let grid = self.grid_position(
+            (cursor_x % tile_width, cursor_y % tile_height),
+            tile_width,
+            self.zoom_factor(),
+        );
+        (grid.column < self.width).then_some(grid)
+    }
+
+    /// File offset of the byte at the given grid position, ignoring folding
+    pub fn offset_at(&self, grid: &GridPosition) -> i64 {
+        let pane_start = if grid.pane == 0 {
+            self.view_start()
+        } else {
+            self.view_start() + self.split_delta
+        };
+        let byte_index = grid.view_index(self.width) / self.pixels_per_byte();
+        self.file_offset(pane_start + byte_index as i64 * self.stride as i64)
+    }
+
+    /// Position of the byte in the given row and column of the current layout, where row 0 is the
+    /// first row at the start of the file with the alignment of the view. `None` outside of the
+    /// grid or the file.
+    pub fn row_column_position(&self, row: i64, column: i64) -> Option<i64> {
+        let columns = (self.width / self.pixels_per_byte()) as i64;
+        if row < 0 || !(0..columns).contains(&column) {
+            return None;
+        }
+        let first_row = self.view_start().rem_euclid(self.row_length());
+        let position = first_row + row * self.row_length() + column * self.stride as i64;
+        (position < self.buffer_length).then_some(position)
+    }
+
+    /// Scroll the row to the top of the view and select the byte in the column, if it exists
+    pub fn go_to_row_column(&mut self, row: i64, column: i64) -> bool {
+        let position = match self.row_column_position(row, column) {
+            Some(position) => position,
+            None => return false,
+        };
+        self.offset = position - column * self.stride as i64;
+        self.offset_fine = 0;
+        let offset = self.file_offset(position);
+        self.selection = Some(Selection {
+            anchor: offset,
+            end: offset,
+        });
+        self.clamp();
+        true
+    }
+
+    /// File offset of the byte under the mouse cursor, limited to the file
+    pub fn cursor_offset(&self) -> Option<i64> {
+        let grid = self.cursor_grid_position()?;
+        Some(
+            self.offset_at(&grid)
+                .clamp(0, (self.buffer_length - 1).max(0)),
+        )
+    }
+
+    /// File offset of a position in the order of the traversal, which is reversed with 'reverse'.
+    /// Positions outside of the file map to offsets outside of the file.
+    pub fn file_offset(&self, position: i64) -> i64 {
+        if self.reverse {
+            self.buffer_length - 1 - position
+        } else {
+            position
+        }
+    }
+
+    /// Part of the window left of the side panel, in which the canvas is shown
+    pub fn canvas_area(
+        &self,
+        window_width: u32,
+        window_height: u32,
+        pixels_per_point: f32,
+    ) -> (u32, u32) {
+        let panel_width = if self.gui_visible {
+            (self.panel_width * pixels_per_point).round() as u32
+        } else {
+            0
+        };
+        (
+            window_width.saturating_sub(panel_width).max(1),
+            window_height.max(1),
+        )
+    }
+
+    /// Size of the canvas for a window of the given size: the window size divided by the smallest
+    /// integer factor which keeps both dimensions within the maximum render size
+    pub fn render_size(&self, window_width: u32, window_height: u32) -> (u32, u32) {
+        let max = self.max_render_size.max(1);
+        let factor = ((window_width + max - 1) / max)
+            .max((window_height + max - 1) / max)
+            .max(1);
+        (
+            (window_width / factor).max(1),
+            (window_height / factor).max(1),
+        )
+    }
+
+    /// Number of rows of the grid which are visible on the canvas
+    pub fn visible_rows(&self) -> isize {
+        self.tile_size().1 / self.zoom_factor()
+    }
+
+    /// Number of bytes in the fully visible rows, by which paging moves the view such that every
+    /// byte is shown exactly once
+    pub fn page_length(&self) -> i64 {
+        self.visible_rows() as i64 * self.row_length()
+    }
+
+    /// The fine offset covers exactly one coarse step, i.e. one row
+    pub fn max_offset_fine(&self) -> i64 {
+        self.row_length() - 1
+    }
+
+    /// Move whole rows from the fine offset into the coarse offset (or borrow them from it), such
+    /// that the fine offset is within its range again. The start of the view does not change,
+    /// unless it would be before the beginning of the file.
+    pub fn carry_offset_fine(&mut self) {
+        let step = self.row_length();
+        self.offset += self.offset_fine.div_euclid(step) * step;
+        self.offset_fine = self.offset_fine.rem_euclid(step);
+
+        if self.offset < 0 {
+            self.offset_fine = (self.offset + self.offset_fine).max(0);
+            self.offset = 0;
+        }
+    }
+
+    /// Show only the byte 'field' of every record: the stride becomes the record size, and the
+    /// view starts at that byte of the record containing the current start. Records are assumed
+    /// to start at the beginning of the file. The width is adjusted such that a row still covers
+    /// about the same number of bytes.
+    pub fn isolate_field(&mut self) {
+        let size = self.record.size.clamp(1, self.max_stride);
+        let field = self.record.field.clamp(0, size - 1) as i64;
+        let row_length = self.row_length();
+
+        let start = self.view_start();
+        let record_start = start - start.rem_euclid(size as i64);
+        self.offset_fine += record_start + field - start;
+
+        self.stride = size;
+        self.width = (row_length / size as i64).max(1) as isize * self.pixels_per_byte();
+        self.clamp();
+    }
+
+    /// Remember the style which was shown before, if the style changed since the last frame
+    pub fn track_pixel_style(&mut self) {
+        match self.tracked_pixel_style {
+            Some(tracked) if tracked != self.pixel_style => {
+                self.previous_pixel_style = Some(tracked);
+            }
+            Some(_) => return,
+            None => {}
+        }
+        self.tracked_pixel_style = Some(self.pixel_style);
+    }
+
+    /// Switch back to the previously used style, such that doing this again toggles between the
+    /// two
+    pub fn toggle_pixel_style(&mut self) {
+        self.track_pixel_style();
+        if let Some(previous) = self.previous_pixel_style {
+            self.previous_pixel_style = Some(self.pixel_style);
+            self.pixel_style = previous;
+            self.tracked_pixel_style = Some(previous);
+        }
+    }
+
+    /// Choose the zoom, width and stride such that the whole file fits on the canvas: with the
+    /// largest zoom if that is possible, and otherwise by skipping bytes with the stride. Doing
+    /// this again goes back to the previous layout.
+    pub fn toggle_fit_to_file(&mut self) {
+        if let Some(saved) = self.fit_restore.take() {
+            self.offset = saved.offset;
+            self.offset_fine = saved.offset_fine;
+            self.width = saved.width;
+            self.stride = saved.stride;
+            self.zoom = saved.zoom;
+            self.clamp();
+            self.status = "Restored the layout".into();
+            return;
+        }
+
+        self.fit_restore = Some(SavedLayout {
+            offset: self.offset,
+            offset_fine: self.offset_fine,
+            width: self.width,
+            stride: self.stride,
+            zoom: self.zoom,
+        });
+
+        let (tile_width, tile_height) = self.tile_size();
+        let pixels_per_byte = self.pixels_per_byte();
+        let capacity = |zoom: isize| {
+            let zoom_factor = 2isize.pow((zoom - 1) as u32);
+            let columns = (tile_width / zoom_factor / pixels_per_byte).max(1);
+            let rows = (tile_height / zoom_factor).max(1);
+            (columns, columns as i64 * rows as i64)
+        };
+
+        let length = self.buffer_length.max(1);
+        let zoom = (self.zoom_range.0..=self.zoom_range.1)
+            .rev()
+            .find(|&zoom| capacity(zoom).1 >= length)
+            .unwrap_or(self.zoom_range.0);
+        let (columns, bytes) = capacity(zoom);
+
+        self.zoom = zoom;
+        self.width = columns * pixels_per_byte;
+        self.stride = ((length + bytes - 1) / bytes) as isize;
+        self.offset = 0;
+        self.offset_fine = 0;
+
+        self.status = if self.stride > self.max_stride {
+            format!(
+                "The file is too large to fit, even when showing 1 of every {} bytes",
+                self.max_stride
+            )
+        } else if self.stride > 1 {
+            format!("Showing 1 of every {} bytes of the whole file", self.stride)
+        } else {
+            "Showing the whole file".into()
+        };
+        self.clamp();
+    }
+
+    /// Bring all settings back into their valid ranges
+    pub fn clamp(&mut self) {
+        self.zoom = self.zoom.max(self.zoom_range.0);
+        self.zoom = self.zoom.min(self.zoom_range.1);
+
+        self.width = self.width.max(1);
+        self.width = self.width.min(self.max_width());
+        // Every row has to start at a byte boundary
+        self.width -= self.width % self.pixels_per_byte();
+        self.width = self.width.max(self.pixels_per_byte());
+
+        self.stride = self.stride.max(1);
+        self.stride = self.stride.min(self.max_stride);
+
+        self.carry_offset_fine();
+        self.offset = self.offset.max(0);
+        self.offset = self.offset.min(self.buffer_length);
+
+        self.split_delta = self.split_delta.max(0);
+        self.split_delta = self.split_delta.min(self.buffer_length);
+
+        self.max_render_size = self.max_render_size.clamp(256, 8192);
+        self.gui_scale = self.gui_scale.clamp(0.5, 3.0);
+
+        self.record.size = self.record.size.clamp(1, self.max_stride);
+        self.record.field = self.record.field.clamp(0, self.record.size - 1);
+
+        self.interleave.streams = self.interleave.streams.clamp(2, 256);
+        self.interleave.sample_size = self.interleave.sample_size.clamp(1, 256);
+        self.interleave.stream = self.interleave.stream.clamp(0, self.interleave.streams - 1);
+    }
+
+    /// Whether the bytes are transformed before they are shown, instead of being read directly
+    /// from the file
+    pub fn is_transformed(&self) -> bool {
+        self.fold.enabled || self.interleave.enabled || self.reverse
+    }
+
+    /// Description of the transforms applied to the bytes before they are shown, if any
+    pub fn transform_description(&self) -> Option<String> {
+        let interleave = &self.interleave;
+        let interleave = interleave.enabled.then(|| {
+            format!(
+                "stream {} of {} ({} byte samples)",
+                interleave.stream, interleave.streams, interleave.sample_size
+            )
+        });
+
+        let fold = &self.fold;
+        let fold = fold.enabled.then(|| {
+            let mode = match fold.mode {
+                FoldMode::Xor => "XOR",
+                FoldMode::Max => "max",
+            };
+            format!(
+                "fold ({}, period {}, {} copies)",
+                mode, fold.period, fold.copies
+            )
+        });
+
+        let steps: Vec<String> = [
+            self.reverse.then(|| "reversed".to_string()),
+            interleave,
+            fold,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!steps.is_empty()).then(|| steps.join(", then "))
+    }
+
+    /// Offset in the file as shown to the user: either as file offset, or as virtual address
+    pub fn display_offset(&self, offset: i64) -> String {
+        if self.show_addresses {
+            format!("address 0x{:x}", self.address_base.wrapping_add(offset))
+        } else {
+            format!("offset {} (0x{:x})", offset, offset)
+        }
+    }
+
+    /// Index of the record which contains the byte at the given offset, and the index of the byte
+    /// within that record. Records have the configured record size, or the stride otherwise.
+    pub fn record_position(&self, offset: i64) -> Option<(i64, i64)> {
+        let size = if self.record.enabled {
+            self.record.size
+        } else {
+            self.stride
+        } as i64;
+        (size > 1).then(|| (offset.div_euclid(size), offset.rem_euclid(size)))
+    }
+
+    /// Short description of the current view, used in crash reports
+    pub fn summary(&self) -> String {
+        format!(
+            "offset: {} (0x{:x}), offset_fine: {}, width: {}, stride: {}, zoom: {}, \
+             pixel style: {:?}, file size: {}",
+            self.offset,
+            self.offset,
+            self.offset_fine,
+            self.width,
+            self.stride,
+            self.zoom,
+            self.pixel_style,
+            self.buffer_length
+        )
+    }
+
+    pub fn max_fold_period(&self) -> i64 {
+        1 << 20
     }
 
     pub fn max_width(&self) -> isize {
@@ -89,20 +1325,454 @@ impl Default for Settings {
             offset_fine: 0,
             stride: 1,
             max_stride: 128,
-            pixel_style: PixelStyle::Colorful,
-            datatype_settings: DatatypeSettings {
-                datatype: GuiDatatype::Integer16,
-                signedness: Signedness::Unsigned,
-                endianness: Endianness::Little,
+            stride_sampling: StrideSampling::Skip,
+            stride_hatch: true,
+            split_view: false,
+            split_delta: 0,
+            fold: FoldSettings {
+                enabled: false,
+                period: 1024,
+                copies: 16,
+                mode: FoldMode::Xor,
+            },
+            nibble_mode: NibbleMode::Off,
+            bit_mask: BitMaskSettings {
+                enabled: false,
+                mask: 0x80,
+                shift: 7,
+            },
+            contrast: ContrastSettings::default(),
+            record: RecordSettings {
+                enabled: false,
+                size: 16,
+                field: 0,
+            },
+            profile: ProfileSettings {
+                enabled: false,
+                signed: false,
+            },
+            interleave: InterleaveSettings {
+                enabled: false,
+                streams: 2,
+                sample_size: 2,
+                stream: 0,
             },
+            reverse: false,
+            compare: CompareSettings {
+                enabled: false,
+                count: 2,
+                styles: [
+                    PixelStyle::Colorful,
+                    PixelStyle::Grayscale,
+                    PixelStyle::Category,
+                    PixelStyle::GradientMagma,
+                ],
+            },
+            scaling_filter: ScalingFilter::Nearest,
+            pixel_style: PixelStyle::Colorful,
+            previous_pixel_style: None,
+            tracked_pixel_style: None,
+            style_settings: StyleSettings::default(),
+            color_scale: ColorScale::default(),
+            inspector: Inspector::default(),
+            regions: Regions::default(),
             buffer_length: 0,
+            hole_length: 0,
+            show_holes: true,
+            padding: PaddingSettings::default(),
+            tile_data: false,
             canvas_width: WIDTH as isize,
-            value_range: (0.0, 100.0),
+            canvas_height: HEIGHT as isize,
+            max_render_size: 2048,
+            cursor: None,
+            crosshair_visible: false,
+            text_overlay: false,
             hex_view_visible: false,
+            hex_view_transformed: false,
             hex_view: "".into(),
             hex_ascii: "".into(),
+            column_profile: ColumnProfile::default(),
+            sparklines: Sparklines::default(),
+            row_graph: RowGraph::default(),
+            versions: VersionSettings::default(),
+            overlay: OverlaySettings::default(),
+            mirror: MirrorSettings::default(),
+            spectrum: SpectrumSettings::default(),
+            entropy_timeline: EntropyTimeline::default(),
+            width_scores: WidthScores::default(),
+            row_correlation: None,
+            annotations: AnnotationSettings::default(),
+            export: ExportSettings::default(),
+            search: SearchSettings::default(),
+            gui_visible: true,
+            help_visible: false,
+            gui_scale: 1.0,
+            panel_width: 200.0,
+            status: "".into(),
+            address_base: 0,
+            show_addresses: false,
+            fit_restore: None,
+            loading: None,
+            entropy_jump_requested: None,
+            analysis_progress: None,
+            editor_command: editor::DEFAULT_EDITOR_COMMAND.into(),
+            editor_requested: false,
+            reload_requested: false,
+            keep_layout: false,
+            clipboard_requested: false,
+            copy_image_requested: false,
+            selection: None,
+            pinned_row: None,
+            pin_row_requested: false,
+            startup: None,
             gui_wants_keyboard: false,
             gui_wants_mouse: false,
         }
     }
 }
+
+#[test]
+fn selection_range_in_both_directions() {
+    let forward = Selection { anchor: 4, end: 9 };
+    let backward = Selection { anchor: 9, end: 4 };
+    assert_eq!(forward.range(), 4..10);
+    assert_eq!(backward.range(), 4..10);
+}
+
+#[test]
+fn offset_fine_carries_into_offset() {
+    let mut settings = Settings {
+        width: 16,
+        stride: 2,
+        offset: 64,
+        offset_fine: 31,
+        ..Settings::new(1024)
+    };
+    settings.carry_offset_fine();
+    assert_eq!((settings.offset, settings.offset_fine), (64, 31));
+
+    settings.offset_fine += 1;
+    settings.carry_offset_fine();
+    assert_eq!((settings.offset, settings.offset_fine), (96, 0));
+
+    settings.offset_fine += 2 * 32 + 5;
+    settings.carry_offset_fine();
+    assert_eq!((settings.offset, settings.offset_fine), (160, 5));
+}
+
+#[test]
+fn offset_fine_borrows_from_offset() {
+    let mut settings = Settings {
+        width: 16,
+        stride: 2,
+        offset: 64,
+        offset_fine: 0,
+        ..Settings::new(1024)
+    };
+    settings.offset_fine -= 1;
+    settings.carry_offset_fine();
+    assert_eq!((settings.offset, settings.offset_fine), (32, 31));
+
+    settings.offset_fine -= 2 * 32;
+    settings.carry_offset_fine();
+    assert_eq!((settings.offset, settings.offset_fine), (0, 0));
+
+    settings.offset = 5;
+    settings.offset_fine = -3;
+    settings.carry_offset_fine();
+    assert_eq!((settings.offset, settings.offset_fine), (0, 2));
+}
+
+#[test]
+fn canvas_area_leaves_room_for_the_panel() {
+    let mut settings = Settings {
+        panel_width: 250.0,
+        gui_visible: true,
+        ..Settings::default()
+    };
+    assert_eq!(settings.canvas_area(1000, 600, 2.0), (500, 600));
+    assert_eq!(settings.canvas_area(400, 600, 2.0), (1, 600));
+    settings.gui_visible = false;
+    assert_eq!(settings.canvas_area(1000, 600, 2.0), (1000, 600));
+}
+
+#[test]
+fn render_size_stays_within_maximum() {
+    let settings = Settings {
+        max_render_size: 2048,
+        ..Settings::default()
+    };
+    assert_eq!(settings.render_size(1366, 1024), (1366, 1024));
+    assert_eq!(settings.render_size(5120, 2160), (1706, 720));
+    assert_eq!(settings.render_size(0, 0), (1, 1));
+}
+
+#[test]
+fn isolate_field_of_records() {
+    let mut settings = Settings {
+        width: 64,
+        offset: 100,
+        record: RecordSettings {
+            enabled: true,
+            size: 16,
+            field: 3,
+        },
+        ..Settings::new(1024)
+    };
+    settings.isolate_field();
+    assert_eq!(settings.view_start(), 99);
+    assert_eq!(settings.stride, 16);
+    assert_eq!(settings.width, 4);
+}
+
+#[test]
+fn page_length_follows_zoom_and_stride() {
+    let mut settings = Settings {
+        canvas_height: 100,
+        width: 16,
+        ..Settings::new(1 << 20)
+    };
+    assert_eq!(settings.page_length(), 100 * 16);
+    settings.zoom = 2;
+    settings.stride = 3;
+    assert_eq!(settings.page_length(), 50 * 16 * 3);
+}
+
+#[test]
+fn record_position_of_offset() {
+    let mut settings = Settings {
+        stride: 4,
+        ..Settings::new(1024)
+    };
+    assert_eq!(settings.record_position(9), Some((2, 1)));
+    settings.record = RecordSettings {
+        enabled: true,
+        size: 16,
+        field: 0,
+    };
+    assert_eq!(settings.record_position(35), Some((2, 3)));
+    settings.record.enabled = false;
+    settings.stride = 1;
+    assert_eq!(settings.record_position(35), None);
+}
+
+#[test]
+fn contrast_stretches_range() {
+    let contrast = ContrastSettings {
+        enabled: true,
+        locked: false,
+        min: 0x10,
+        max: 0x20,
+    }
+    .byte_map();
+    assert_eq!(
+        (
+            contrast[0x00],
+            contrast[0x10],
+            contrast[0x18],
+            contrast[0x20],
+            contrast[0xff]
+        ),
+        (0, 0, 127, 255, 255)
+    );
+
+    let uniform = ContrastSettings {
+        min: 0x40,
+        max: 0x40,
+        ..ContrastSettings::default()
+    }
+    .byte_map();
+    assert_eq!((uniform[0x3f], uniform[0x40], uniform[0x41]), (0, 0, 0));
+}
+
+#[test]
+fn bit_mask_extracts_bits() {
+    let sign_bit = BitMaskSettings {
+        enabled: true,
+        mask: 0x80,
+        shift: 7,
+    }
+    .byte_map();
+    assert_eq!(
+        (sign_bit[0x7f], sign_bit[0x80], sign_bit[0xff]),
+        (0, 255, 255)
+    );
+
+    let high_nibble = BitMaskSettings {
+        enabled: true,
+        mask: 0xf0,
+        shift: 4,
+    }
+    .byte_map();
+    assert_eq!(
+        (high_nibble[0x0f], high_nibble[0x10], high_nibble[0xf0]),
+        (0, 17, 255)
+    );
+}
+
+#[test]
+fn fit_to_file_and_back() {
+    let mut settings = Settings {
+        canvas_width: 1000,
+        canvas_height: 1000,
+        width: 300,
+        zoom: 2,
+        offset: 512,
+        ..Settings::new(1 << 20)
+    };
+    settings.toggle_fit_to_file();
+    assert_eq!(
+        (settings.zoom, settings.width, settings.stride),
+        (1, 1000, 2)
+    );
+    assert_eq!(settings.view_start(), 0);
+
+    settings.toggle_fit_to_file();
+    assert_eq!(
+        (settings.zoom, settings.width, settings.stride),
+        (2, 300, 1)
+    );
+    assert_eq!(settings.offset, 512);
+
+    settings.buffer_length = 10_000;
+    settings.toggle_fit_to_file();
+    assert_eq!(
+        (settings.zoom, settings.width, settings.stride),
+        (4, 125, 1)
+    );
+}
+
+#[test]
+fn interleaved_stream_indices() {
+    let interleave = InterleaveSettings {
+        enabled: true,
+        streams: 2,
+        sample_size: 2,
+        stream: 1,
+    };
+    let indices: Vec<i64> = (0..6).map(|i| interleave.source_index(i)).collect();
+    assert_eq!(indices, [2, 3, 6, 7, 10, 11]);
+}
+
+#[test]
+fn offsets_as_addresses() {
+    let mut settings = Settings {
+        address_base: 0x400000,
+        ..Settings::default()
+    };
+    assert_eq!(settings.display_offset(0x10), "offset 16 (0x10)");
+    settings.show_addresses = true;
+    assert_eq!(settings.display_offset(0x10), "address 0x400010");
+}
+
+#[test]
+fn offsets_of_reverse_traversal() {
+    let mut settings = Settings {
+        width: 16,
+        reverse: true,
+        ..Settings::new(256)
+    };
+    let grid = settings.grid_position((2, 1), settings.canvas_width, 1);
+    assert_eq!(settings.offset_at(&grid), 255 - 18);
+    settings.reverse = false;
+    assert_eq!(settings.offset_at(&grid), 18);
+}
+
+#[test]
+fn toggle_between_last_two_styles() {
+    let mut settings = Settings::default();
+    settings.toggle_pixel_style();
+    assert_eq!(settings.pixel_style, PixelStyle::Colorful);
+
+    settings.pixel_style = PixelStyle::Grayscale;
+    settings.track_pixel_style();
+    settings.pixel_style = PixelStyle::Entropy;
+    settings.toggle_pixel_style();
+    assert_eq!(settings.pixel_style, PixelStyle::Grayscale);
+    settings.toggle_pixel_style();
+    assert_eq!(settings.pixel_style, PixelStyle::Entropy);
+    settings.track_pixel_style();
+    settings.toggle_pixel_style();
+    assert_eq!(settings.pixel_style, PixelStyle::Grayscale);
+}
+
+#[test]
+fn row_and_column_of_the_layout() {
+    let mut settings = Settings {
+        width: 16,
+        stride: 2,
+        offset: 100,
+        offset_fine: 5,
+        ..Settings::new(1000)
+    };
+    // Rows are 32 bytes long and aligned to the view start 105
+    assert_eq!(settings.row_column_position(0, 0), Some(9));
+    assert_eq!(settings.row_column_position(3, 2), Some(9 + 3 * 32 + 4));
+    assert_eq!(settings.row_column_position(0, 16), None);
+    assert_eq!(settings.row_column_position(31, 0), None);
+
+    assert!(settings.go_to_row_column(3, 2));
+    assert_eq!(settings.view_start(), 9 + 3 * 32);
+    assert_eq!(settings.selection.unwrap().range(), 109..110);
+}
+
+#[test]
+fn reset_keeps_a_pending_load() {
+    let mut settings = Settings {
+        width: 77,
+        loading: Some(LoadingState {
+            file_size: 1 << 30,
+            bytes_read: 0,
+            started: false,
+            choice: None,
+            download: false,
+        }),
+        ..Settings::new(0)
+    };
+    settings.reset();
+    assert_ne!(settings.width, 77);
+    assert_eq!(
+        settings.loading.map(|loading| loading.file_size),
+        Some(1 << 30)
+    );
+}
+
+#[test]
+fn multi_byte_styles_skip_past_their_cell() {
+    let mut settings = Settings {
+        stride: 3,
+        ..Settings::new(0)
+    };
+    assert!(settings.skips_bytes());
+    settings.pixel_style = PixelStyle::Rgb;
+    assert!(!settings.skips_bytes());
+    settings.pixel_style = PixelStyle::Rgba;
+    settings.stride = 4;
+    assert!(!settings.skips_bytes());
+    settings.pixel_style = PixelStyle::Datatype;
+    assert!(settings.skips_bytes());
+    settings.style_settings.datatype.datatype = GuiDatatype::Float32;
+    assert!(!settings.skips_bytes());
+    settings.stride_sampling = StrideSampling::Mean;
+    settings.pixel_style = PixelStyle::Grayscale;
+    assert!(!settings.skips_bytes());
+}
+
+#[test]
+fn reset_goes_back_to_the_startup_style() {
+    let mut settings = Settings {
+        width: 300,
+        pixel_style: PixelStyle::Entropy,
+        ..Settings::new(1 << 20)
+    };
+    settings.style_settings.grayscale_gamma = 2.0;
+    settings.keep_as_startup();
+
+    settings.width = 77;
+    settings.pixel_style = PixelStyle::Grayscale;
+    settings.style_settings.grayscale_gamma = 1.0;
+    settings.reset();
+    assert_eq!(settings.width, 300);
+    assert_eq!(settings.pixel_style, PixelStyle::Entropy);
+    assert_eq!(settings.style_settings.grayscale_gamma, 2.0);
+}
@@ -0,0 +1,49 @@
+use rustfft::{num_complex::Complex, FftPlanner};
+
+/// Magnitudes of the discrete Fourier transform of the byte values, for the frequencies 1 to
+/// n/2 (in cycles per n bytes). The mean is removed first, so that it does not dominate.
+pub fn magnitudes(values: &[u8]) -> Vec<f32> {
+    if values.len() < 2 {
+        return vec![];
+    }
+
+    let mean = values.iter().map(|&b| b as f32).sum::<f32>() / values.len() as f32;
+    let mut buffer: Vec<Complex<f32>> = values
+        .iter()
+        .map(|&b| Complex::new(b as f32 - mean, 0.0))
+        .collect();
+    FftPlanner::new()
+        .plan_fft_forward(buffer.len())
+        .process(&mut buffer);
+
+    buffer[1..=values.len() / 2]
+        .iter()
+        .map(|c| c.norm())
+        .collect()
+}
+
+/// Period in bytes of the strongest frequency in the spectrum of 'len' bytes, considering only
+/// periods up to 'max_period'
+pub fn strongest_period(magnitudes: &[f32], len: usize, max_period: f64) -> Option<f64> {
+    magnitudes
+        .iter()
+        .enumerate()
+        .map(|(i, &magnitude)| (len as f64 / (i + 1) as f64, magnitude))
+        .filter(|&(period, magnitude)| period <= max_period && magnitude > 0.0)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(period, _)| period)
+}
+
+#[test]
+fn period_of_records() {
+    let values: Vec<u8> = (0..4096)
+        .map(|i| if i % 16 < 3 { 200 } else { i as u8 % 7 })
+        .collect();
+    let magnitudes = magnitudes(&values);
+    assert_eq!(magnitudes.len(), 2048);
+    assert_eq!(
+        strongest_period(&magnitudes, values.len(), 1024.0),
+        Some(16.0)
+    );
+    assert_eq!(strongest_period(&[0.0; 8], 16, 1024.0), None);
+}
@@ -1,5 +1,8 @@
+use std::borrow::Cow;
 use std::convert::TryInto;
 
+use serde::{Deserialize, Serialize};
+
 use crate::datatype::{Datatype, Endianness};
 use crate::view::View;
 
@@ -14,54 +17,151 @@ fn rgba_from_color(color: colorgrad::Color) -> Color {
     ]
 }
 
-pub trait Style {
-    fn init(&mut self, _view: &View) {}
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color;
+/// Color of the gradient at position 't', which is clamped to the range 0..1. Positions from
+/// remapped values can be outside of that range or NaN, which is treated as the start.
+fn gradient_color(gradient: &colorgrad::Gradient, t: f64) -> Color {
+    let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+    rgba_from_color(gradient.at(t))
+}
+
+pub trait Style: Sync {
+    /// Prepare for drawing the first 'len' indices of each of the views
+    fn init(&mut self, _views: &[View], _len: isize) {}
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color;
 }
 
-pub struct Colorful;
+/// Set of colors for the styles with distinct colors (like 'Category')
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Palette {
+    Standard,
+    /// Colors which stay distinguishable with color vision deficiencies, based on the
+    /// Okabe-Ito palette
+    ColorBlindSafe,
+}
+
+pub struct Colorful {
+    pub palette: Palette,
+}
 
 impl Style for Colorful {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some(b) = view.byte_at(view_index) {
-            [b, b.overflowing_mul(2).0, b.overflowing_mul(4).0, 255]
+            match self.palette {
+                Palette::Standard => [b, b.overflowing_mul(2).0, b.overflowing_mul(4).0, 255],
+                // Vary along the blue-yellow axis instead of red-green
+                Palette::ColorBlindSafe => [b, b, b.overflowing_mul(4).0, 255],
+            }
         } else {
             [0, 0, 0, 0]
         }
     }
 }
-pub struct Grayscale;
+pub struct Grayscale {
+    byte_color: [Color; 256],
+}
+
+impl Grayscale {
+    /// Gamma values larger than one brighten the dark values, smaller ones darken them
+    pub fn with_gamma(gamma: f32) -> Self {
+        let mut byte_color = [[0, 0, 0, 0]; 256];
+        for (byte, color) in byte_color.iter_mut().enumerate() {
+            let value = ((byte as f32 / 255.0).powf(1.0 / gamma) * 255.0).round() as u8;
+            *color = [value, value, value, 255];
+        }
+
+        Grayscale { byte_color }
+    }
+}
 
 impl Style for Grayscale {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some(b) = view.byte_at(view_index) {
-            [b, b, b, 255]
+            self.byte_color[b as usize]
         } else {
             [0, 0, 0, 0]
         }
     }
 }
 
-pub struct Category;
+/// Colors of the categories: alphanumeric, punctuation, whitespace, other ASCII, non-ASCII
+const CATEGORY_COLORS: [Color; 5] = [
+    [60, 178, 255, 255],
+    [0, 129, 213, 255],
+    [162, 218, 255, 255],
+    [60, 255, 137, 255],
+    [249, 53, 94, 255],
+];
+
+/// The categories in colors of the Okabe-Ito palette: sky blue, blue, yellow, bluish green and
+/// orange
+const CATEGORY_COLORS_COLOR_BLIND_SAFE: [Color; 5] = [
+    [86, 180, 233, 255],
+    [0, 114, 178, 255],
+    [240, 228, 66, 255],
+    [0, 158, 115, 255],
+    [230, 159, 0, 255],
+];
+
+/// Custom color for a range of bytes, which takes precedence over the built-in categories
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryRule {
+    pub first: u8,
+    pub last: u8,
+    pub color: [u8; 3],
+}
 
-impl Style for Category {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
-        if let Some(b) = view.byte_at(view_index) {
-            if b == 0x00 {
+pub struct Category {
+    byte_color: [Color; 256],
+}
+
+impl Category {
+    /// The first matching rule determines the color of a byte. With 'shade_other', the bytes
+    /// outside of the ASCII categories get darker shades of their color for lower values.
+    pub fn new(palette: Palette, rules: &[CategoryRule], shade_other: bool) -> Self {
+        let colors = match palette {
+            Palette::Standard => &CATEGORY_COLORS,
+            Palette::ColorBlindSafe => &CATEGORY_COLORS_COLOR_BLIND_SAFE,
+        };
+
+        let mut byte_color = [[0, 0, 0, 0]; 256];
+        for (b, color) in byte_color.iter_mut().enumerate() {
+            let b = b as u8;
+            let rule = rules
+                .iter()
+                .find(|rule| (rule.first..=rule.last).contains(&b));
+            *color = if let Some(rule) = rule {
+                let [r, g, b] = rule.color;
+                [r, g, b, 255]
+            } else if b == 0x00 {
                 [0, 0, 0, 255]
             } else if b == 0xFF {
                 [255, 255, 255, 255]
             } else if b.is_ascii_alphanumeric() {
-                [60, 178, 255, 255]
+                colors[0]
             } else if b.is_ascii_punctuation() {
-                [0, 129, 213, 255]
+                colors[1]
             } else if b.is_ascii_whitespace() {
-                [162, 218, 255, 255]
+                colors[2]
             } else if b.is_ascii() {
-                [60, 255, 137, 255]
+                colors[3]
+            } else if shade_other {
+                let brightness = 0.25 + 0.75 * (b - 0x80) as f32 / (0xfe - 0x80) as f32;
+                let shade = |channel: u8| (channel as f32 * brightness).round() as u8;
+                let [r, g, b, a] = colors[4];
+                [shade(r), shade(g), shade(b), a]
             } else {
-                [249, 53, 94, 255]
-            }
+                colors[4]
+            };
+        }
+
+        Category { byte_color }
+    }
+}
+
+impl Style for Category {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
+        if let Some(b) = view.byte_at(view_index) {
+            self.byte_color[b as usize]
         } else {
             [0, 0, 0, 0]
         }
@@ -76,24 +176,285 @@ impl ColorGradient {
     pub fn new(gradient: colorgrad::Gradient) -> Self {
         let mut byte_color = [[0, 0, 0, 0]; 256];
         for (byte, color) in byte_color.iter_mut().enumerate() {
-            let gradient_color = gradient.at((byte as f64) / 255.0f64);
-            *color = rgba_from_color(gradient_color);
+            *color = gradient_color(&gradient, (byte as f64) / 255.0f64);
         }
 
         ColorGradient { byte_color }
     }
+
+    /// The same gradient from the other end, e.g. light-on-dark instead of dark-on-light
+    pub fn reversed(mut self) -> Self {
+        self.byte_color.reverse();
+        self
+    }
+}
+
+impl ColorGradient {
+    pub fn color(&self, value: u8) -> Color {
+        self.byte_color[value as usize]
+    }
 }
 
 impl Style for ColorGradient {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some(b) = view.byte_at(view_index) {
-            self.byte_color[b as usize]
+            self.color(b)
+        } else {
+            [0, 0, 0, 0]
+        }
+    }
+}
+
+/// Colors each byte by the (wrapping) difference to its predecessor in the view, centered at 128.
+/// A byte without a predecessor is treated as having a delta of zero.
+pub struct Delta {
+    gradient: ColorGradient,
+}
+
+impl Delta {
+    pub fn new(gradient: colorgrad::Gradient) -> Self {
+        Delta {
+            gradient: ColorGradient::new(gradient),
+        }
+    }
+}
+
+impl Style for Delta {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
+        if let Some(b) = view.byte_at(view_index) {
+            let delta = view
+                .byte_at(view_index - 1)
+                .map(|predecessor| b.wrapping_sub(predecessor))
+                .unwrap_or(0);
+            self.gradient.color(delta.wrapping_add(128))
+        } else {
+            [0, 0, 0, 0]
+        }
+    }
+}
+
+/// Colors each byte by its position in the data, ignoring its value. Layout transforms which
+/// move data around show up as discontinuities in the otherwise smooth gradient.
+pub struct Position {
+    gradient: colorgrad::Gradient,
+}
+
+impl Position {
+    pub fn new(gradient: colorgrad::Gradient) -> Self {
+        Position { gradient }
+    }
+}
+
+impl Style for Position {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
+        if let Some(position) = view.position_at(view_index) {
+            gradient_color(&self.gradient, position)
         } else {
             [0, 0, 0, 0]
         }
     }
 }
 
+/// Height of the relief for a byte value of 255, in grid cells
+const RELIEF_HEIGHT: f32 = 4.0;
+
+/// Share of the brightness of the relief which does not depend on the light
+const RELIEF_AMBIENT: f32 = 0.3;
+
+/// Treats the byte values as the heights of a terrain which is lit from the top left, such that
+/// ridges and plateaus in the data stand out. The surface normal of every byte comes from the
+/// slopes to its neighbors in the grid of the given width, and the shading darkens the gradient
+/// color of its height.
+pub struct Relief {
+    gradient: ColorGradient,
+    width: isize,
+    light: [f32; 3],
+}
+
+impl Relief {
+    pub fn new(gradient: colorgrad::Gradient, width: isize) -> Self {
+        let norm = 3f32.sqrt();
+        Relief {
+            gradient: ColorGradient::new(gradient),
+            width: width.max(1),
+            light: [-1.0 / norm, -1.0 / norm, 1.0 / norm],
+        }
+    }
+
+    /// Brightness between the ambient share and one of the byte at the given index. Neighbors
+    /// outside of the data or the row count as the same height.
+    fn shade(&self, view: &View, view_index: isize, height: u8) -> f32 {
+        let column = view_index.rem_euclid(self.width);
+        let height_at = |index: isize| view.byte_at(index).unwrap_or(height) as f32;
+        let left = if column > 0 {
+            height_at(view_index - 1)
+        } else {
+            height as f32
+        };
+        let right = if column < self.width - 1 {
+            height_at(view_index + 1)
+        } else {
+            height as f32
+        };
+        let above = height_at(view_index - self.width);
+        let below = height_at(view_index + self.width);
+
+        let scale = RELIEF_HEIGHT / 255.0 / 2.0;
+        let normal = [(left - right) * scale, (above - below) * scale, 1.0];
+        let length = normal.iter().map(|c| c * c).sum::<f32>().sqrt();
+        let lit = normal
+            .iter()
+            .zip(self.light)
+            .map(|(n, l)| n * l)
+            .sum::<f32>()
+            / length;
+        RELIEF_AMBIENT + (1.0 - RELIEF_AMBIENT) * lit.max(0.0)
+    }
+}
+
+impl Style for Relief {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
+        if let Some(b) = view.byte_at(view_index) {
+            let shade = self.shade(view, view_index, b);
+            let [r, g, b, a] = self.gradient.color(b);
+            let apply = |c: u8| (c as f32 * shade).round() as u8;
+            [apply(r), apply(g), apply(b), a]
+        } else {
+            [0, 0, 0, 0]
+        }
+    }
+}
+
+/// Runs of this length and longer are shown with the brightest color
+const RUN_LENGTH_MAX_LOG2: f64 = 24.0;
+
+fn run_length_level(len: usize) -> u8 {
+    ((len as f64).log2() / RUN_LENGTH_MAX_LOG2 * 255.0).min(255.0) as u8
+}
+
+/// Brightness level of every byte in 'data', depending on the (log-scaled) length of the run of
+/// identical bytes it belongs to.
+pub fn run_length_levels(data: &[u8]) -> Vec<u8> {
+    let mut levels = Vec::with_capacity(data.len());
+    let mut start = 0;
+    while start < data.len() {
+        let len = data[start..]
+            .iter()
+            .take_while(|&&b| b == data[start])
+            .count();
+        levels.resize(start + len, run_length_level(len));
+        start += len;
+    }
+    levels
+}
+
+/// The levels of 'run_length_levels' for the first 'len' indices of the view only. Runs are
+/// measured in all of the data of the view, up to the length at which the level saturates.
+pub fn view_run_length_levels(view: &View, len: isize) -> Vec<u8> {
+    let data = view.data();
+    let max_run = 1usize << RUN_LENGTH_MAX_LOG2 as u32;
+    let mut levels = Vec::with_capacity(len.max(0) as usize);
+    // Consecutive indices often hit the same run, which is then only measured once
+    let mut run = 0..0;
+    let mut level = 0;
+    for view_index in 0..len {
+        let index = match view.data_index(view_index) {
+            Some(index) if index < data.len() => index,
+            _ => {
+                levels.push(0);
+                continue;
+            }
+        };
+        if !run.contains(&index) {
+            let b = data[index];
+            let before = data[index.saturating_sub(max_run)..index]
+                .iter()
+                .rev()
+                .take_while(|&&other| other == b)
+                .count();
+            let after = data[index..(index + max_run).min(data.len())]
+                .iter()
+                .take_while(|&&other| other == b)
+                .count();
+            run = index - before..index + after;
+            level = run_length_level(run.len());
+        }
+        levels.push(level);
+    }
+    levels
+}
+
+enum RunLengthLevels<'a> {
+    /// Levels of all of the data, indexed like it
+    Data(Cow<'a, [u8]>),
+    /// Levels of the first indices of each view given to 'init', with the address of the data
+    /// and the first data index of the view to tell them apart
+    Views(Vec<(usize, Option<usize>, Vec<u8>)>),
+}
+
+/// Colors each byte by the length of the run of identical bytes it belongs to, so that padding
+/// and fill regions stand out from noisy data
+pub struct RunLength<'a> {
+    levels: RunLengthLevels<'a>,
+    gradient: ColorGradient,
+}
+
+impl<'a> RunLength<'a> {
+    /// With the levels of all of the data of the views, which must have been computed with
+    /// 'run_length_levels'
+    pub fn new(levels: Cow<'a, [u8]>, gradient: colorgrad::Gradient) -> Self {
+        RunLength {
+            levels: RunLengthLevels::Data(levels),
+            gradient: ColorGradient::new(gradient),
+        }
+    }
+
+    /// With the levels computed for the visible part of the views only, in 'init'. This is for
+    /// data which is too large to keep the levels of all of it.
+    pub fn visible(gradient: colorgrad::Gradient) -> Self {
+        RunLength {
+            levels: RunLengthLevels::Views(vec![]),
+            gradient: ColorGradient::new(gradient),
+        }
+    }
+}
+
+impl Style for RunLength<'_> {
+    fn init(&mut self, views: &[View], len: isize) {
+        if let RunLengthLevels::Views(levels) = &mut self.levels {
+            *levels = views
+                .iter()
+                .map(|view| {
+                    (
+                        view.data().as_ptr() as usize,
+                        view.data_index(0),
+                        view_run_length_levels(view, len),
+                    )
+                })
+                .collect();
+        }
+    }
+
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
+        let level = match &self.levels {
+            RunLengthLevels::Data(levels) => view
+                .data_index(view_index)
+                .and_then(|data_index| levels.get(data_index)),
+            RunLengthLevels::Views(views) => views
+                .iter()
+                .find(|(data, first, _)| {
+                    *data == view.data().as_ptr() as usize && *first == view.data_index(0)
+                })
+                .filter(|_| view.byte_at(view_index).is_some())
+                .and_then(|(_, _, levels)| levels.get(usize::try_from(view_index).ok()?)),
+        };
+        match level {
+            Some(&level) => self.gradient.color(level),
+            None => [0, 0, 0, 0],
+        }
+    }
+}
+
 pub struct DatatypeStyle {
     datatype: Datatype,
     endianness: Endianness,
@@ -102,16 +463,28 @@ pub struct DatatypeStyle {
 }
 
 impl DatatypeStyle {
-    pub fn new(datatype: Datatype, endianness: Endianness, range: (f32, f32)) -> Self {
+    /// With 'diverging', the range is made symmetric around zero, and negative values are shown
+    /// in blue, zero in white and positive values in red.
+    pub fn new(
+        datatype: Datatype,
+        endianness: Endianness,
+        range: (f32, f32),
+        diverging: bool,
+    ) -> Self {
         let num_colors = 1024;
-        let mut colors = Vec::new();
-        colors.reserve(num_colors);
+        let mut colors = Vec::with_capacity(num_colors);
 
-        let gradient = colorgrad::plasma();
+        let (gradient, range) = if diverging {
+            let magnitude = range.0.abs().max(range.1.abs());
+            (colorgrad::rd_bu(), (-magnitude, magnitude))
+        } else {
+            (colorgrad::plasma(), range)
+        };
         for i in 0..num_colors {
-            colors.push(rgba_from_color(
-                gradient.at((i as f64) / (num_colors as f64)),
-            ));
+            let t = (i as f64) / (num_colors as f64);
+            // The red-blue gradient starts with red, but negative values should be blue
+            let t = if diverging { 1.0 - t } else { t };
+            colors.push(gradient_color(&gradient, t));
         }
 
         DatatypeStyle {
@@ -136,7 +509,7 @@ impl DatatypeStyle {
 }
 
 impl Style for DatatypeStyle {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         view.slice_at(view_index, self.datatype.size())
             .and_then(|slice| self.datatype.read_as_float_from(slice, self.endianness))
             .map(|t| {
@@ -147,24 +520,114 @@ impl Style for DatatypeStyle {
     }
 }
 
-pub struct Rgba;
+/// Colors words which point into the address space '[base, end)' by their target, such that
+/// pointers to the same region share a color. Other words are shown as dark gray values, zero
+/// words as black.
+pub struct Pointer {
+    word_size: usize,
+    endianness: Endianness,
+    base: u64,
+    end: u64,
+    gradient: ColorGradient,
+}
+
+impl Pointer {
+    pub fn new(word_size: usize, endianness: Endianness, base: u64, end: u64) -> Self {
+        Pointer {
+            word_size: word_size.clamp(1, 8),
+            endianness,
+            base,
+            end,
+            gradient: ColorGradient::new(colorgrad::turbo()),
+        }
+    }
+
+    fn read_word(&self, slice: &[u8]) -> u64 {
+        let mut bytes = [0u8; 8];
+        match self.endianness {
+            Endianness::Little => bytes[..slice.len()].copy_from_slice(slice),
+            Endianness::Big => bytes[8 - slice.len()..].copy_from_slice(slice),
+        }
+        match self.endianness {
+            Endianness::Little => u64::from_le_bytes(bytes),
+            Endianness::Big => u64::from_be_bytes(bytes),
+        }
+    }
+}
+
+impl Style for Pointer {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
+        match view
+            .slice_at(view_index, self.word_size)
+            .map(|slice| self.read_word(slice))
+        {
+            Some(0) => [0, 0, 0, 255],
+            Some(word) if (self.base..self.end).contains(&word) => {
+                let position = (word - self.base) as u128 * 256 / (self.end - self.base) as u128;
+                self.gradient.color(position as u8)
+            }
+            Some(word) => {
+                let gray = (word as u8) / 4;
+                [gray, gray, gray, 255]
+            }
+            None => [0, 0, 0, 0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlphaMode {
+    /// Ignore the alpha channel, as many dumps contain meaningless alpha values
+    Opaque,
+    /// Blend the color over a checkerboard pattern according to its alpha value
+    Checkerboard,
+    /// Only show the alpha channel, as a grayscale value
+    Alpha,
+}
+
+/// Size of a checkerboard square, in grid cells
+const CHECKERBOARD_SIZE: isize = 8;
+
+/// Applies the alpha mode to an RGBA color at the given position of a grid of the given width
+fn apply_alpha(color: Color, alpha_mode: AlphaMode, view_index: isize, width: isize) -> Color {
+    let [r, g, b, a] = color;
+    match alpha_mode {
+        AlphaMode::Opaque => [r, g, b, 255],
+        AlphaMode::Checkerboard => {
+            let x = view_index % width / CHECKERBOARD_SIZE;
+            let y = view_index / width / CHECKERBOARD_SIZE;
+            let background: u16 = if (x + y) % 2 == 0 { 102 } else { 153 };
+            let blend = |c: u8| ((c as u16 * a as u16 + background * (255 - a as u16)) / 255) as u8;
+            [blend(r), blend(g), blend(b), 255]
+        }
+        AlphaMode::Alpha => [a, a, a, 255],
+    }
+}
+
+pub struct Rgba {
+    pub alpha_mode: AlphaMode,
+    pub width: isize,
+}
 
 impl Style for Rgba {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some(int) = view.be_u32_at(view_index) {
-            int.to_be_bytes()
+            apply_alpha(int.to_be_bytes(), self.alpha_mode, view_index, self.width)
         } else {
             [0, 0, 0, 0]
         }
     }
 }
 
-pub struct Abgr;
+pub struct Abgr {
+    pub alpha_mode: AlphaMode,
+    pub width: isize,
+}
 
 impl Style for Abgr {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some(int) = view.be_u32_at(view_index) {
-            int.to_le_bytes()
+            apply_alpha(int.to_le_bytes(), self.alpha_mode, view_index, self.width)
         } else {
             [0, 0, 0, 0]
         }
@@ -174,7 +637,7 @@ impl Style for Abgr {
 pub struct Rgb;
 
 impl Style for Rgb {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some([r, g, b]) = view.rgb_at(view_index) {
             [r, g, b, 255]
         } else {
@@ -186,7 +649,7 @@ impl Style for Rgb {
 pub struct Bgr;
 
 impl Style for Bgr {
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some([b, g, r]) = view.rgb_at(view_index) {
             [r, g, b, 255]
         } else {
@@ -195,10 +658,30 @@ impl Style for Bgr {
     }
 }
 
+/// Shannon entropy of bytes with the given value counts, normalized to the range 0..1
+pub fn entropy_from_counts(counts: &[i32; 256], total: f64) -> f64 {
+    let mut entropy = 0.0f64;
+    for &count in counts {
+        if count > 0 {
+            let p = (count as f64) / total;
+            entropy -= p * p.log2();
+        }
+    }
+    entropy * (1.0f64 / 8.0f64)
+}
+
+/// Normalized Shannon entropy of the bytes
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+    entropy_from_counts(&counts, bytes.len() as f64)
+}
+
 pub struct Entropy {
     window_size: usize,
     window_size_f64: f64,
-    counts: [i32; 256],
     /// Cache the gradient color
     byte_color: [Color; 256],
 }
@@ -208,38 +691,26 @@ impl Entropy {
         let gradient = colorgrad::magma();
         let mut byte_color = [[0, 0, 0, 0]; 256];
         for (byte, color) in byte_color.iter_mut().enumerate() {
-            let gradient_color = gradient.at((byte as f64) / 255.0f64);
-            *color = rgba_from_color(gradient_color);
+            *color = gradient_color(&gradient, (byte as f64) / 255.0f64);
         }
 
         Entropy {
             window_size,
             window_size_f64: window_size as f64,
-            counts: [0; 256],
             byte_color,
         }
     }
 }
 
 impl Style for Entropy {
-    fn init(&mut self, _: &View) {}
-
-    fn color_at_index(&mut self, view: &View, view_index: isize) -> Color {
+    fn color_at_index(&self, view: &View, view_index: isize) -> Color {
         if let Some(bytes) = view.slice_at(view_index, self.window_size) {
-            self.counts.fill(0);
-
+            let mut counts = [0; 256];
             for byte in bytes.iter() {
-                self.counts[*byte as usize] += 1;
+                counts[*byte as usize] += 1;
             }
 
-            let mut entropy = 0.0f64;
-            for count in self.counts {
-                if count > 0 {
-                    let p = (count as f64) / self.window_size_f64;
-                    entropy -= p * p.log2();
-                }
-            }
-            entropy *= 1.0f64 / 8.0f64;
+            let entropy = entropy_from_counts(&counts, self.window_size_f64);
 
             let discretized_entropy: usize = ((entropy * self.byte_color.len() as f64) as usize)
                 .clamp(0, self.byte_color.len() - 1);
@@ -249,3 +720,102 @@ impl Style for Entropy {
         }
     }
 }
+
+#[test]
+fn gradient_color_of_degenerate_positions() {
+    let gradient = colorgrad::magma();
+    let start = gradient_color(&gradient, 0.0);
+    let end = gradient_color(&gradient, 1.0);
+    assert_eq!(gradient_color(&gradient, f64::NAN), start);
+    assert_eq!(gradient_color(&gradient, -1e300), start);
+    assert_eq!(gradient_color(&gradient, f64::NEG_INFINITY), start);
+    assert_eq!(gradient_color(&gradient, 2.5), end);
+    assert_eq!(gradient_color(&gradient, f64::INFINITY), end);
+}
+
+#[test]
+fn run_length_levels_cover_runs() {
+    let levels = run_length_levels(&[1, 2, 2, 3, 3, 3, 3]);
+    assert_eq!(levels.len(), 7);
+    assert_eq!(levels[0], 0);
+    assert_eq!(levels[1], levels[2]);
+    assert!(levels[3] > levels[1]);
+    assert!(levels[3..].iter().all(|&level| level == levels[3]));
+}
+
+#[test]
+fn visible_run_length_levels_match_the_whole_data() {
+    let data = [5, 1, 2, 2, 3, 3, 3, 3, 3, 3, 4];
+    let levels = run_length_levels(&data);
+    let view = View::new(&data, 1, 2);
+    let mut style = RunLength::visible(colorgrad::greys());
+    style.init(std::slice::from_ref(&view), 6);
+    let whole = RunLength::new(Cow::Borrowed(&levels), colorgrad::greys());
+    for view_index in 0..6 {
+        assert_eq!(
+            style.color_at_index(&view, view_index),
+            whole.color_at_index(&view, view_index),
+            "at {}",
+            view_index
+        );
+    }
+    assert_eq!(view_run_length_levels(&view, 6)[2], levels[5]);
+}
+
+#[test]
+fn relief_shades_slopes() {
+    let style = Relief::new(colorgrad::greys(), 3);
+    let flat = [100; 9];
+    let flat_shade = style.shade(&View::new(&flat, 0, 1), 4, 100);
+    assert!((flat_shade - (RELIEF_AMBIENT + (1.0 - RELIEF_AMBIENT) / 3f32.sqrt())).abs() < 1e-6);
+
+    // A slope rising to the right faces the light on the left, one falling to the right does not
+    let rising = [0, 100, 200, 0, 100, 200, 0, 100, 200];
+    let falling = [200, 100, 0, 200, 100, 0, 200, 100, 0];
+    assert!(style.shade(&View::new(&rising, 0, 1), 4, 100) > flat_shade);
+    assert!(style.shade(&View::new(&falling, 0, 1), 4, 100) < flat_shade);
+}
+
+#[test]
+fn pointer_classification() {
+    let data = [0, 0, 0, 0, 0x10, 0, 0, 0, 0xff, 0xff, 0, 0];
+    let view = View::new(&data, 0, 4);
+    let style = Pointer::new(4, Endianness::Little, 0, 0x100);
+    assert_eq!(style.color_at_index(&view, 0), [0, 0, 0, 255]);
+    assert_eq!(style.color_at_index(&view, 1), style.gradient.color(0x10));
+    assert_eq!(style.color_at_index(&view, 2), [63, 63, 63, 255]);
+    assert_eq!(style.color_at_index(&view, 3), [0, 0, 0, 0]);
+}
+
+#[test]
+fn category_rules_take_precedence() {
+    let rules = [
+        CategoryRule {
+            first: 0x1b,
+            last: 0x1b,
+            color: [1, 2, 3],
+        },
+        CategoryRule {
+            first: 0x00,
+            last: 0x7f,
+            color: [4, 5, 6],
+        },
+    ];
+    let category = Category::new(Palette::Standard, &rules, false);
+    assert_eq!(category.byte_color[0x1b], [1, 2, 3, 255]);
+    assert_eq!(category.byte_color[b'a' as usize], [4, 5, 6, 255]);
+    assert_eq!(category.byte_color[0xff], [255, 255, 255, 255]);
+}
+
+#[test]
+fn category_shades_other_bytes() {
+    let flat = Category::new(Palette::Standard, &[], false);
+    let shaded = Category::new(Palette::Standard, &[], true);
+    assert_eq!(flat.byte_color[0x80], flat.byte_color[0xfe]);
+    assert_eq!(shaded.byte_color[0xfe], flat.byte_color[0xfe]);
+    assert!(shaded.byte_color[0x80][0] < shaded.byte_color[0xc0][0]);
+    assert_eq!(
+        shaded.byte_color[b'a' as usize],
+        flat.byte_color[b'a' as usize]
+    );
+}
@@ -2,56 +2,109 @@ use std::convert::TryInto;
 
 pub struct View<'a> {
     stride: isize,
-    start: isize,
+    start: i64,
 
     data: &'a [u8],
+    /// Applied to every byte returned by 'byte_at', but not to slices
+    byte_map: Option<&'a [u8; 256]>,
+    /// Indices outside of the data wrap around, such that the data repeats endlessly
+    wrap: bool,
+    /// Positions in the file of the bytes at each view index, for views on a copy of the data
+    positions: Option<&'a [Option<f64>]>,
 }
 
 impl<'a> View<'a> {
-    pub fn new(data: &'a [u8], start: isize, stride: isize) -> Self {
+    pub fn new(data: &'a [u8], start: i64, stride: isize) -> Self {
         assert!(stride >= 1);
 
         View {
             start,
             stride,
             data,
+            byte_map: None,
+            wrap: false,
+            positions: None,
         }
     }
 
+    pub fn with_byte_map(self, byte_map: &'a [u8; 256]) -> Self {
+        View {
+            byte_map: Some(byte_map),
+            ..self
+        }
+    }
+
+    pub fn wrapping(self) -> Self {
+        View { wrap: true, ..self }
+    }
+
+    /// Report the given positions (indexed by view index) instead of those in the data, e.g.
+    /// when the data is a transformed copy of the file
+    pub fn with_positions(self, positions: &'a [Option<f64>]) -> Self {
+        View {
+            positions: Some(positions),
+            ..self
+        }
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
     // pub fn len(&self) -> isize {
     //     // the length of the view is (len - start)/stride, but rounded towards
     //     // infinity. that's what the "+ stride - 1" part is for.
     //     (self.data.len() as isize - self.start + self.stride - 1) / self.stride
     // }
 
-    fn data_index(&self, view_index: isize) -> usize {
-        (self.start + view_index * self.stride)
-            .try_into()
-            .expect("positive index")
+    /// Computes the index into the underlying data in 64 bit arithmetic. Returns `None` if the
+    /// index is negative or can not be represented as a `usize` (on 32 bit platforms), in which
+    /// case it is out of range of the data anyway.
+    pub fn data_index(&self, view_index: isize) -> Option<usize> {
+        let index = self.start + (view_index as i64) * (self.stride as i64);
+        if self.wrap && !self.data.is_empty() {
+            return index.rem_euclid(self.data.len() as i64).try_into().ok();
+        }
+        index.try_into().ok()
     }
 
     pub fn byte_at(&self, view_index: isize) -> Option<u8> {
-        self.data.get(self.data_index(view_index)).copied()
+        let byte = self
+            .data_index(view_index)
+            .and_then(|data_index| self.data.get(data_index))
+            .copied()?;
+        Some(match self.byte_map {
+            Some(byte_map) => byte_map[byte as usize],
+            None => byte,
+        })
+    }
+
+    /// Position of the byte in the underlying data (or in the file, see 'with_positions'),
+    /// normalized to the range 0..1
+    pub fn position_at(&self, view_index: isize) -> Option<f64> {
+        if let Some(positions) = self.positions {
+            return *positions.get(usize::try_from(view_index).ok()?)?;
+        }
+        let data_index = self
+            .data_index(view_index)
+            .filter(|&i| i < self.data.len())?;
+        Some(data_index as f64 / self.data.len() as f64)
     }
 
     pub fn be_u32_at(&self, view_index: isize) -> Option<u32> {
-        let data_index = self.data_index(view_index);
-        self.data
-            .get(data_index..(data_index + 4))
+        self.slice_at(view_index, 4)
             .and_then(|slice| slice.try_into().ok())
             .map(u32::from_be_bytes)
     }
 
     pub fn rgb_at(&self, view_index: isize) -> Option<[u8; 3]> {
-        let data_index = self.data_index(view_index);
-        self.data
-            .get(data_index..(data_index + 3))
+        self.slice_at(view_index, 3)
             .and_then(|slice| slice.try_into().ok())
     }
 
     pub fn slice_at(&self, view_index: isize, len: usize) -> Option<&[u8]> {
-        let data_index = self.data_index(view_index);
-        self.data.get(data_index..(data_index + len))
+        let data_index = self.data_index(view_index)?;
+        self.data.get(data_index..data_index.checked_add(len)?)
     }
 }
 
@@ -156,3 +209,68 @@ fn view_access_u32() {
     assert_eq!(view.be_u32_at(1), Some(0xaabbccdd));
     assert_eq!(view.be_u32_at(2), None);
 }
+
+#[test]
+fn view_access_beyond_4gib() {
+    use crate::settings::Settings;
+
+    let settings = Settings {
+        offset: 5 << 30,
+        offset_fine: 3,
+        stride: 2,
+        buffer_length: 6 << 30,
+        ..Default::default()
+    };
+    assert_eq!(settings.view_start(), (5 << 30) + 3);
+
+    let data: Vec<u8> = vec![0, 1, 2];
+    let view = View::new(&data, settings.view_start(), settings.stride);
+
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(view.data_index(4), Some((5usize << 30) + 3 + 8));
+    #[cfg(target_pointer_width = "32")]
+    assert_eq!(view.data_index(4), None);
+
+    assert_eq!(view.byte_at(0), None);
+    assert_eq!(view.byte_at(4), None);
+    assert_eq!(view.slice_at(0, 4), None);
+    assert_eq!(view.be_u32_at(0), None);
+    assert_eq!(view.rgb_at(0), None);
+}
+
+#[test]
+fn view_access_before_start_of_data() {
+    let data: Vec<u8> = vec![0, 1, 2];
+    let view = View::new(&data, 1, 1);
+
+    assert_eq!(view.byte_at(-1), Some(0));
+    assert_eq!(view.byte_at(-2), None);
+    assert_eq!(view.slice_at(-2, 2), None);
+}
+
+#[test]
+fn view_access_wrapping() {
+    let data: Vec<u8> = vec![0, 1, 2];
+    let view = View::new(&data, 1, 2).wrapping();
+
+    assert_eq!(view.byte_at(0), Some(1));
+    assert_eq!(view.byte_at(1), Some(0));
+    assert_eq!(view.byte_at(2), Some(2));
+    assert_eq!(view.byte_at(-1), Some(2));
+    assert_eq!(View::new(&[], 0, 1).wrapping().byte_at(1), None);
+}
+
+#[test]
+fn view_position_access() {
+    let data = [0u8; 4];
+    let view = View::new(&data, 1, 2);
+    assert_eq!(view.position_at(0), Some(0.25));
+    assert_eq!(view.position_at(1), Some(0.75));
+    assert_eq!(view.position_at(2), None);
+
+    let positions = [Some(0.5), None];
+    let view = view.with_positions(&positions);
+    assert_eq!(view.position_at(0), Some(0.5));
+    assert_eq!(view.position_at(1), None);
+    assert_eq!(view.position_at(2), None);
+}
@@ -0,0 +1,358 @@
+use binocle::binocle::Binocle;
+use binocle::headless::render_to_vec;
+use binocle::settings::{PixelStyle, Selection};
+
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+/// The first rows of the canvas are tinted at the beginning of the file
+const FIRST_UNTINTED_ROW: isize = 3;
+
+/// A view of the bytes 0x00 to 0xff in grayscale, on a small canvas
+fn ramp(width: isize) -> Binocle {
+    let mut binocle = Binocle::from_bytes((0..=255).collect());
+    let settings = &mut binocle.settings;
+    settings.canvas_width = 32;
+    settings.canvas_height = 32;
+    settings.width = width;
+    settings.pixel_style = PixelStyle::Grayscale;
+    settings.clamp();
+    binocle
+}
+
+fn pixel(frame: &[u8], canvas_width: isize, (x, y): (isize, isize)) -> [u8; 4] {
+    let i = ((y * canvas_width + x) * 4) as usize;
+    frame[i..i + 4].try_into().unwrap()
+}
+
+#[test]
+fn pixels_show_their_bytes() {
+    let binocle = ramp(16);
+    let frame = render_to_vec(&binocle);
+    for (x, y) in [(0, 3), (5, 4), (0, 10), (15, 15)] {
+        let b = (y * 16 + x) as u8;
+        assert_eq!(pixel(&frame, 32, (x, y)), [b, b, b, 255], "at {:?}", (x, y));
+    }
+}
+
+#[test]
+fn dead_zone_right_of_the_grid_is_transparent() {
+    let binocle = ramp(16);
+    let frame = render_to_vec(&binocle);
+    assert_eq!(pixel(&frame, 32, (16, FIRST_UNTINTED_ROW)), TRANSPARENT);
+    assert_eq!(pixel(&frame, 32, (31, 10)), TRANSPARENT);
+}
+
+#[test]
+fn bytes_past_the_end_are_transparent() {
+    let binocle = ramp(16);
+    let frame = render_to_vec(&binocle);
+    // 256 bytes fill exactly 16 rows of 16 bytes
+    assert_eq!(pixel(&frame, 32, (0, 16)), TRANSPARENT);
+
+    let mut binocle = ramp(16);
+    binocle.settings.offset = 250;
+    let frame = render_to_vec(&binocle);
+    assert_eq!(pixel(&frame, 32, (5, 0)), [255, 255, 255, 255]);
+    assert_eq!(pixel(&frame, 32, (6, 0)), TRANSPARENT);
+}
+
+#[test]
+fn zoom_and_stride() {
+    let mut binocle = ramp(8);
+    binocle.settings.zoom = 2;
+    binocle.settings.stride = 2;
+    let frame = render_to_vec(&binocle);
+    // Every byte covers 2×2 pixels, and every second byte is skipped
+    assert_eq!(pixel(&frame, 32, (0, 4)), [32, 32, 32, 255]);
+    assert_eq!(pixel(&frame, 32, (1, 5)), [32, 32, 32, 255]);
+    assert_eq!(pixel(&frame, 32, (2, 4)), [34, 34, 34, 255]);
+    assert_eq!(pixel(&frame, 32, (0, 6)), [48, 48, 48, 255]);
+}
+
+#[test]
+fn skipping_stride_hatches_the_canvas() {
+    let mut binocle = ramp(8);
+    binocle.settings.stride = 2;
+    let hatched = render_to_vec(&binocle);
+    binocle.settings.stride_hatch = false;
+    let plain = render_to_vec(&binocle);
+    // Pixels on the diagonal lines are darker, the others are unchanged
+    assert!(pixel(&hatched, 32, (4, 4))[0] < pixel(&plain, 32, (4, 4))[0]);
+    assert_eq!(pixel(&hatched, 32, (5, 4)), pixel(&plain, 32, (5, 4)));
+}
+
+#[test]
+fn reverse_traversal_starts_at_the_end() {
+    let mut binocle = ramp(16);
+    binocle.settings.reverse = true;
+    binocle.settings.offset = 16;
+    let frame = render_to_vec(&binocle);
+    // The view starts 16 bytes before the end and goes backwards
+    assert_eq!(pixel(&frame, 32, (0, 3)), [191, 191, 191, 255]);
+    assert_eq!(pixel(&frame, 32, (15, 14)), [0, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, (0, 15)), TRANSPARENT);
+}
+
+#[test]
+fn text_overlay_tints_printable_bytes() {
+    let mut binocle = ramp(16);
+    binocle.settings.text_overlay = true;
+    let frame = render_to_vec(&binocle);
+    // 'A' is mixed with green, DEL keeps its gray
+    assert_eq!(pixel(&frame, 32, (1, 4)), [32, 160, 32, 255]);
+    assert_eq!(pixel(&frame, 32, (15, 7)), [127, 127, 127, 255]);
+}
+
+#[test]
+fn fixture_file_fills_the_canvas() {
+    use clap::Parser;
+
+    let options = binocle::options::CliOptions::parse_from(["binocle", "tests/bag-small"]);
+    let mut binocle = Binocle::new(options, false).unwrap();
+    binocle.settings.canvas_width = 64;
+    binocle.settings.canvas_height = 64;
+    binocle.settings.width = 64;
+    let frame = render_to_vec(&binocle);
+    assert!(frame[(FIRST_UNTINTED_ROW * 64 * 4) as usize..]
+        .chunks_exact(4)
+        .all(|pixel| pixel[3] == 255));
+}
+
+#[test]
+fn switching_versions_keeps_the_view() {
+    use clap::Parser;
+
+    let options = binocle::options::CliOptions::parse_from([
+        "binocle",
+        "tests/bag-small",
+        "tests/bag-small-edited",
+    ]);
+    let mut binocle = Binocle::new(options, false).unwrap();
+    let bag_length = binocle.settings.buffer_length;
+    binocle.settings.offset = 100;
+
+    binocle.settings.versions.step_requested = Some(1);
+    binocle.update_versions();
+    assert_eq!(binocle.settings.versions.current, 1);
+    assert_eq!(binocle.filename(), "tests/bag-small-edited");
+    assert_ne!(binocle.settings.buffer_length, bag_length);
+    assert_eq!(binocle.settings.offset, 100);
+
+    binocle.settings.versions.step_requested = Some(-3);
+    binocle.update_versions();
+    assert_eq!(binocle.settings.versions.current, 0);
+    assert_eq!(binocle.settings.buffer_length, bag_length);
+}
+
+#[test]
+fn opening_a_file_keeps_the_layout_if_asked() {
+    use clap::Parser;
+
+    let options = binocle::options::CliOptions::parse_from(["binocle", "tests/bag-small"]);
+    let mut binocle = Binocle::new(options, false).unwrap();
+    let default_width = binocle.settings.width;
+    binocle.settings.width = 100;
+    binocle.settings.offset = 200;
+
+    binocle.settings.keep_layout = true;
+    binocle.open("tests/bag-small-edited").unwrap();
+    assert_eq!(binocle.filename(), "tests/bag-small-edited");
+    assert_eq!(binocle.settings.width, 100);
+    assert_eq!(binocle.settings.offset, 200);
+
+    binocle.settings.keep_layout = false;
+    binocle.open("tests/bag-small").unwrap();
+    assert_eq!(binocle.settings.width, default_width);
+    assert_eq!(binocle.settings.offset, 0);
+    assert!(binocle.open("tests/does-not-exist").is_err());
+}
+
+#[test]
+fn detached_selection_becomes_a_version() {
+    let mut binocle = ramp(16);
+    binocle.settings.selection = Some(Selection {
+        anchor: 0x47,
+        end: 0x40,
+    });
+    binocle.settings.versions.detach_requested = true;
+    binocle.update_detach();
+    assert_eq!(binocle.settings.buffer_length, 8);
+    assert_eq!(binocle.settings.versions.detached_from, Some(0x40));
+    assert_eq!(
+        (
+            binocle.settings.versions.current,
+            binocle.settings.versions.count
+        ),
+        (1, 2)
+    );
+
+    // The original stays available as the previous version
+    binocle.settings.versions.step_requested = Some(-1);
+    binocle.update_versions();
+    assert_eq!(binocle.settings.buffer_length, 256);
+    assert_eq!(binocle.settings.versions.detached_from, None);
+}
+
+#[test]
+fn data_without_a_file_is_not_reloaded() {
+    let mut binocle = Binocle::from_bytes(vec![1, 2, 3]);
+    assert_eq!(binocle.path(), None);
+    binocle.settings.reload_requested = true;
+    binocle.update_reload();
+    assert_eq!(binocle.settings.buffer_length, 3);
+    assert!(binocle.settings.status.contains("not a file"));
+}
+
+#[test]
+fn overlay_shows_the_run_lengths_of_the_next_version() {
+    use clap::Parser;
+
+    let options = binocle::options::CliOptions::parse_from([
+        "binocle",
+        "tests/bag-small",
+        "tests/bag-small-edited",
+        "--style",
+        "run-length",
+    ]);
+    let mut binocle = Binocle::new(options, false).unwrap();
+    let overlay = &mut binocle.settings.overlay;
+    overlay.enabled = true;
+    overlay.alpha = 1.0;
+    let blended = render_to_vec(&binocle);
+
+    binocle.settings.overlay.enabled = false;
+    binocle.settings.versions.step_requested = Some(1);
+    binocle.update_versions();
+    let next = render_to_vec(&binocle);
+    // Only the cells of the grid are blended, and the full rows of the shorter next version
+    let settings = &binocle.settings;
+    assert_eq!(settings.zoom_factor(), 1);
+    let full_rows = settings.buffer_length as isize / settings.width;
+    for y in 0..full_rows.min(settings.canvas_height) {
+        for x in 0..settings.width.min(settings.canvas_width) {
+            let position = (x, y);
+            assert_eq!(
+                pixel(&blended, settings.canvas_width, position),
+                pixel(&next, settings.canvas_width, position)
+            );
+        }
+    }
+}
+
+#[test]
+fn positional_styles_are_not_mirrored() {
+    let mut binocle = ramp(16);
+    binocle.settings.pixel_style = PixelStyle::Position;
+    let plain = render_to_vec(&binocle);
+    let mirror = &mut binocle.settings.mirror;
+    mirror.enabled = true;
+    mirror.axis = 100.0;
+    assert_eq!(render_to_vec(&binocle), plain);
+
+    binocle.settings.pixel_style = PixelStyle::Grayscale;
+    let plain = render_to_vec(&binocle);
+    binocle.settings.mirror.enabled = false;
+    assert_ne!(render_to_vec(&binocle), plain);
+}
+
+#[test]
+fn opening_waits_for_a_pending_load() {
+    let mut binocle = Binocle::from_bytes(vec![]);
+    binocle.settings.loading = Some(binocle::settings::LoadingState {
+        file_size: 1 << 30,
+        bytes_read: 0,
+        started: true,
+        choice: None,
+        download: false,
+    });
+    assert!(binocle.open("tests/bag-small").is_err());
+    assert_eq!(binocle.filename(), "<memory>");
+    assert!(binocle.settings.loading.is_some());
+}
+
+#[test]
+fn detached_bytes_are_not_a_file() {
+    use clap::Parser;
+
+    let options = binocle::options::CliOptions::parse_from(["binocle", "tests/bag-small"]);
+    let mut binocle = Binocle::new(options, false).unwrap();
+    binocle.settings.selection = Some(Selection {
+        anchor: 0x40,
+        end: 0x47,
+    });
+    binocle.settings.versions.detach_requested = true;
+    binocle.update_detach();
+    assert_eq!(binocle.filename(), "tests/bag-small [0x40..0x48]");
+    assert_eq!(binocle.path(), None);
+    assert!(!binocle.settings.versions.file_backed);
+
+    binocle.settings.versions.step_requested = Some(1);
+    binocle.update_versions();
+    assert_eq!(binocle.path(), Some("tests/bag-small"));
+    assert!(binocle.settings.versions.file_backed);
+}
+
+#[test]
+fn regions_tint_their_bytes_and_follow_the_version() {
+    use clap::Parser;
+
+    let options = binocle::options::CliOptions::parse_from([
+        "binocle",
+        "tests/bag-small",
+        "tests/bag-small-edited",
+    ]);
+    let mut binocle = Binocle::new(options, false).unwrap();
+    let settings = &mut binocle.settings;
+    settings.canvas_width = 32;
+    settings.canvas_height = 32;
+    settings.width = 16;
+    settings.clamp();
+    let plain = render_to_vec(&binocle);
+
+    binocle.settings.selection = Some(Selection {
+        anchor: 0x40,
+        end: 0x4f,
+    });
+    binocle.settings.regions.add_requested = true;
+    binocle.update_regions();
+    binocle.settings.selection = None;
+    let frame = render_to_vec(&binocle);
+    let width = binocle.settings.canvas_width;
+    assert_ne!(pixel(&frame, width, (0, 4)), pixel(&plain, width, (0, 4)));
+    assert_eq!(
+        pixel(&frame, width, (0, FIRST_UNTINTED_ROW)),
+        pixel(&plain, width, (0, FIRST_UNTINTED_ROW))
+    );
+
+    binocle.settings.versions.step_requested = Some(1);
+    binocle.update_versions();
+    assert!(binocle.settings.regions.map.regions.is_empty());
+    binocle.settings.versions.step_requested = Some(1);
+    binocle.update_versions();
+    assert_eq!(binocle.settings.regions.map.regions.len(), 1);
+    assert!(binocle.settings.regions.modified);
+}
+
+#[test]
+fn position_follows_the_file_offsets_of_transformed_data() {
+    let mut binocle = ramp(16);
+    binocle.settings.pixel_style = PixelStyle::Position;
+    let plain = binocle.render(32, 32, 1).pixels;
+    let cell = |pixels: &[u8], position| pixel(pixels, 32, position);
+
+    binocle.settings.reverse = true;
+    let reversed = binocle.render(32, 32, 1).pixels;
+    assert_eq!(cell(&reversed, (0, 0)), cell(&plain, (15, 15)));
+    assert_eq!(cell(&reversed, (15, 15)), cell(&plain, (0, 0)));
+
+    binocle.settings.reverse = false;
+    let interleave = &mut binocle.settings.interleave;
+    interleave.enabled = true;
+    interleave.streams = 2;
+    interleave.sample_size = 1;
+    interleave.stream = 1;
+    let interleaved = binocle.render(32, 32, 1).pixels;
+    assert_eq!(cell(&interleaved, (0, 0)), cell(&plain, (1, 0)));
+    assert_eq!(cell(&interleaved, (1, 0)), cell(&plain, (3, 0)));
+    assert_eq!(cell(&interleaved, (0, 1)), cell(&plain, (1, 2)));
+}
@@ -0,0 +1,90 @@
+use std::process::Command;
+
+/// Golden hashes of frames rendered from the 'bag-small' fixture. If a change to the rendering
+/// is intended, update the hashes with the output of 'binocle tests/bag-small --render-hash …'.
+const GOLDEN_HASHES: &[(&[&str], &str)] = &[
+    (
+        &[],
+        "955b7a88d12c4d60eb19924fe1a28e311c57dfd2bdcdb199be521b6dc5335f29",
+    ),
+    (
+        &["--style", "grayscale"],
+        "e93952eb3b7a2e78794cc2934b800992b8776f5ae81e072333c9b512e4e9e18a",
+    ),
+    (
+        &["--style", "category", "--width", "256"],
+        "19a33fb4dcd9771cbd9da749df92c3c244abd9263d4194bee26d98bc519476bf",
+    ),
+    (
+        &["--style", "entropy", "--width", "512", "--offset", "4096"],
+        "263458c1e1ce20ad273f8317af450e6cfed1121718a7138f3587465f91f80081",
+    ),
+    (
+        &[
+            "--style", "rgb", "--width", "64", "--offset", "13221", "--zoom", "3",
+        ],
+        "ae066116dec3a5a93cbe30d9e750c886cf183404521e4104ff3ac7c7984e0f29",
+    ),
+    (
+        &[
+            "--style",
+            "gradient-magma",
+            "--stride",
+            "4",
+            "--width",
+            "128",
+        ],
+        "babe646e6152efd5646fecfdad0893514f324e30ad704b8be04874905da77986",
+    ),
+    (
+        &[
+            "--style", "entropy", "--width", "24", "--offset", "1000", "--zoom", "7",
+        ],
+        "c91e1bdec8a83dc74110af13de442656eb2ca6b0417452fdbc900849577f107d",
+    ),
+];
+
+#[test]
+fn render_hashes_match_golden_hashes() {
+    for (args, golden_hash) in GOLDEN_HASHES {
+        let output = Command::new(env!("CARGO_BIN_EXE_binocle"))
+            .arg("tests/bag-small")
+            .arg("--render-hash")
+            .args(*args)
+            .output()
+            .expect("binocle can be executed");
+        assert!(output.status.success());
+
+        let hash = String::from_utf8(output.stdout).expect("valid UTF-8");
+        assert_eq!(hash.trim(), *golden_hash, "render hash for {:?}", args);
+    }
+}
+
+#[test]
+fn filmstrip_stops_at_the_end_of_the_file() {
+    let output_dir = std::env::temp_dir().join(format!("binocle-filmstrip-{}", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_binocle"))
+        .arg("tests/bag-small")
+        .args(["--filmstrip", "--step", "0x4000", "--count", "4"])
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .output()
+        .expect("binocle can be executed");
+    assert!(output.status.success());
+
+    // The fixture is about 33 KiB large, so only the offsets 0, 0x4000 and 0x8000 are in the file
+    let mut images: Vec<_> = std::fs::read_dir(&output_dir)
+        .expect("output directory exists")
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    images.sort();
+    assert_eq!(
+        images,
+        [
+            "bag-small-0000.png",
+            "bag-small-0001.png",
+            "bag-small-0002.png"
+        ]
+    );
+    std::fs::remove_dir_all(&output_dir).unwrap();
+}